@@ -0,0 +1,756 @@
+use ndarray::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::solver::{self, EnumerationCache};
+use crate::topology::Topology;
+
+/// The maximum number of layouts to try when generating a board that can be
+/// solved without guessing. There is no time limit, so that the same seed and
+/// first click always give the same board, however fast the computer is.
+const NO_GUESS_ATTEMPTS: usize = 1_000;
+
+/// The highest mine density at which a board that can be solved without
+/// guessing can usually be found within the number of attempts.
+pub const MAX_NO_GUESS_DENSITY: f32 = 0.25;
+
+pub type Pos = (usize, usize);
+
+/// All information about a Minesweeper game
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Board {
+    /// The size of the board (height, width)
+    size: Pos,
+    /// The shape of the grid, which decides which tiles are neighbors.
+    #[serde(default)]
+    topology: Topology,
+    /// Whether the edges of the board wrap around, so that tiles on opposite
+    /// edges are neighbors.
+    #[serde(default)]
+    wrap: bool,
+    /// The largest number of mines that a single tile can hold.
+    #[serde(default = "one_mine_per_tile")]
+    mines_per_tile: u8,
+    /// An array of mines and numbers
+    tiles: Array2<Tile>,
+    /// An array describing visibility, flags, and question marks.
+    tilestates: Array2<TileState>,
+    /// The number of flags remaining. Mines that have been uncovered (which
+    /// can happen without losing when playing with more than one life) count
+    /// as flagged.
+    flags_left: isize,
+    /// The number of undiscovered safe squares.
+    safe_squares_left: isize,
+    /// The seed used to generate the board.
+    seed: u64,
+    /// The random number generator used to place (and relocate) mines. This is
+    /// not saved, since it is only needed before the first click.
+    #[serde(skip, default = "unseeded_rng")]
+    rng: StdRng,
+}
+
+impl Board {
+    pub fn get_size(&self) -> &Pos {
+        &self.size
+    }
+    pub fn get_topology(&self) -> &Topology {
+        &self.topology
+    }
+    pub fn is_wrapping(&self) -> bool {
+        self.wrap
+    }
+    pub fn get_mines_per_tile(&self) -> u8 {
+        self.mines_per_tile
+    }
+    pub fn get_tiles(&self) -> &Array2<Tile> {
+        &self.tiles
+    }
+    pub fn get_tilestates(&self) -> &Array2<TileState> {
+        &self.tilestates
+    }
+    pub fn get_flags_left(&self) -> isize {
+        self.flags_left
+    }
+    pub fn get_safe_squares_left(&self) -> isize {
+        self.safe_squares_left
+    }
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SavedTile")]
+pub enum Tile {
+    /// A tile with one or more mines on it.
+    Mine(u8),
+    Safe(u8),
+}
+
+impl Tile {
+    /// Return the number of mines on the tile.
+    pub fn get_mines(self) -> u8 {
+        match self {
+            Tile::Mine(n) => n,
+            Tile::Safe(_) => 0,
+        }
+    }
+    pub fn is_mine(self) -> bool {
+        self.get_mines() > 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SavedTileState")]
+pub enum TileState {
+    Hidden,
+    /// A tile with one or more flags on it.
+    Flagged(u8),
+    QuestionMark,
+    Uncovered,
+}
+
+impl TileState {
+    /// Return the number of flags on the tile.
+    pub fn get_flags(self) -> u8 {
+        match self {
+            TileState::Flagged(n) => n,
+            _ => 0,
+        }
+    }
+}
+
+/// A tile as it is read from a save. Before a tile could hold more than one
+/// mine, a mine was saved as `"Mine"`, with no count.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedTile {
+    Current(CurrentTile),
+    Old(OldTile),
+}
+
+#[derive(Deserialize)]
+enum CurrentTile {
+    Mine(u8),
+    Safe(u8),
+}
+
+#[derive(Deserialize)]
+enum OldTile {
+    Mine,
+}
+
+impl From<SavedTile> for Tile {
+    fn from(saved: SavedTile) -> Self {
+        match saved {
+            SavedTile::Current(CurrentTile::Mine(n)) => Tile::Mine(n),
+            SavedTile::Current(CurrentTile::Safe(n)) => Tile::Safe(n),
+            SavedTile::Old(OldTile::Mine) => Tile::Mine(1),
+        }
+    }
+}
+
+/// A tile state as it is read from a save. Before a tile could hold more than
+/// one flag, a flag was saved as `"Flagged"`, with no count.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedTileState {
+    Current(CurrentTileState),
+    Old(OldTileState),
+}
+
+#[derive(Deserialize)]
+enum CurrentTileState {
+    Hidden,
+    Flagged(u8),
+    QuestionMark,
+    Uncovered,
+}
+
+#[derive(Deserialize)]
+enum OldTileState {
+    Flagged,
+}
+
+impl From<SavedTileState> for TileState {
+    fn from(saved: SavedTileState) -> Self {
+        match saved {
+            SavedTileState::Current(CurrentTileState::Hidden) => TileState::Hidden,
+            SavedTileState::Current(CurrentTileState::Flagged(n)) => TileState::Flagged(n),
+            SavedTileState::Current(CurrentTileState::QuestionMark) => TileState::QuestionMark,
+            SavedTileState::Current(CurrentTileState::Uncovered) => TileState::Uncovered,
+            SavedTileState::Old(OldTileState::Flagged) => TileState::Flagged(1),
+        }
+    }
+}
+
+impl Board {
+    /// Reveal tiles adjacent to a discovered number tile it has the correct
+    /// number of adjacent flags. Return a (possibly empty) vector of the
+    /// coordinates of tiles that changed state.
+    fn reveal_adjacent(&mut self, pos: Pos) -> Vec<Pos> {
+        let mut result = vec![];
+        // Ensure that the tile is safe.
+        if let Tile::Safe(n) = self.tiles[pos] {
+            // Check the number of neighboring flags. Uncovered mines count as
+            // flagged.
+            let mut flags = 0;
+            for neighbor_pos in self.neighbor_coords(pos) {
+                flags += match self.tilestates[neighbor_pos] {
+                    TileState::Uncovered => self.tiles[neighbor_pos].get_mines(),
+                    tilestate => tilestate.get_flags(),
+                };
+            }
+            if flags == n {
+                // Reveal all non-flag neighbors.
+                for neighbor_pos in self.neighbor_coords(pos).collect::<Vec<_>>() {
+                    match self.tilestates[neighbor_pos] {
+                        TileState::Flagged(_) | TileState::Uncovered => (),
+                        TileState::Hidden | TileState::QuestionMark => {
+                            result.append(&mut self.reveal(neighbor_pos));
+                        }
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Reveal a single tile. Return a (possibly empty) vector of the
+    /// coordinates of tiles that changed state.
+    fn reveal(&mut self, pos: Pos) -> Vec<Pos> {
+        match self.tilestates[pos] {
+            TileState::Hidden | TileState::QuestionMark => {
+                self.tilestates[pos] = TileState::Uncovered;
+                self.count_uncovered(pos, 1);
+                // Cascade zeros (recursively).
+                let mut result = vec![pos];
+                if self.tiles[pos] == Tile::Safe(0) {
+                    for neighbor_pos in self.neighbor_coords(pos).collect::<Vec<_>>() {
+                        if self.tilestates[neighbor_pos] == TileState::Hidden {
+                            result.append(&mut self.reveal(neighbor_pos));
+                        }
+                    }
+                }
+                result
+            }
+            TileState::Flagged(_) | TileState::Uncovered => vec![],
+        }
+    }
+
+    /// Cycle the flag/question mark on a single tile, skipping the question
+    /// mark if `question_marks` is false. If a tile can hold more than one
+    /// mine, then the number of flags goes up to that many before the flags
+    /// are removed. Return a (possibly empty) vector of
+    /// the coordinates of tiles that changed state (which should be one, at
+    /// most).
+    fn cycle_flag(&mut self, pos: Pos, question_marks: bool) -> Vec<Pos> {
+        let mut result = vec![pos];
+        self.tilestates[pos] = match self.tilestates[pos] {
+            TileState::Hidden => {
+                self.flags_left -= 1;
+                TileState::Flagged(1)
+            }
+            TileState::Flagged(n) if n < self.mines_per_tile => {
+                self.flags_left -= 1;
+                TileState::Flagged(n + 1)
+            }
+            TileState::Flagged(n) => {
+                self.flags_left += n as isize;
+                if question_marks {
+                    TileState::QuestionMark
+                } else {
+                    TileState::Hidden
+                }
+            }
+            TileState::QuestionMark => TileState::Hidden,
+            other => {
+                result.pop();
+                other
+            }
+        };
+        result
+    }
+
+    /// Handle a left click on a tile and return a (possibly empty) vector of
+    /// coordinates that changed state as a result.
+    pub fn left_click(&mut self, pos: Pos) -> Vec<Pos> {
+        match self.tilestates[pos] {
+            TileState::Hidden | TileState::QuestionMark => self.reveal(pos),
+            TileState::Flagged(_) => vec![],
+            TileState::Uncovered => self.reveal_adjacent(pos),
+        }
+    }
+
+    /// Handle a right click on a tile and return a (possibly empty) vector of
+    /// coordinates that changed state as a result.
+    pub fn right_click(&mut self, pos: Pos, question_marks: bool) -> Vec<Pos> {
+        self.cycle_flag(pos, question_marks)
+    }
+
+    /// Set the state of a single tile directly, keeping the number of flags
+    /// and safe squares left up to date. This is used to undo and redo actions.
+    pub(crate) fn set_tilestate(&mut self, pos: Pos, tilestate: TileState) {
+        match self.tilestates[pos] {
+            TileState::Flagged(n) => self.flags_left += n as isize,
+            TileState::Uncovered => self.count_uncovered(pos, -1),
+            _ => (),
+        }
+        match tilestate {
+            TileState::Flagged(n) => self.flags_left -= n as isize,
+            TileState::Uncovered => self.count_uncovered(pos, 1),
+            _ => (),
+        }
+        self.tilestates[pos] = tilestate;
+    }
+
+    /// Update the number of flags and safe squares left after a tile is
+    /// uncovered (if `change` is 1) or covered again (if `change` is -1). An
+    /// uncovered mine counts as flagged rather than as a safe square.
+    fn count_uncovered(&mut self, pos: Pos, change: isize) {
+        match self.tiles[pos] {
+            Tile::Mine(n) => self.flags_left -= change * n as isize,
+            Tile::Safe(_) => self.safe_squares_left -= change,
+        }
+    }
+
+    /// Make a new empty board with a given size.
+    pub fn make_empty(size: (usize, usize)) -> Board {
+        Board {
+            size,
+            topology: Topology::Square,
+            wrap: false,
+            mines_per_tile: 1,
+            tiles: Array2::from_elem(size, Tile::Safe(0)),
+            tilestates: Array2::from_elem(size, TileState::Hidden),
+            flags_left: 0,
+            safe_squares_left: (size.0 * size.1) as isize,
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    /// Make a new random board with a given size, shape, and number of mines,
+    /// where each tile can hold up to `mines_per_tile` mines. The same seed,
+    /// size, shape, and number of mines always produce the same board.
+    pub fn make_random(
+        size: (usize, usize),
+        topology: Topology,
+        wrap: bool,
+        mines: usize,
+        mines_per_tile: u8,
+        seed: u64,
+    ) -> Result<Board, &'static str> {
+        if size.0 < 1 || size.1 < 7 {
+            return Err("Board size must be at least 7x1");
+        }
+        if 50 < size.0 || 50 < size.1 {
+            return Err("Board size may not be greater than 50x50");
+        }
+        if mines_per_tile == 0 {
+            return Err("Each tile must be able to hold at least one mine");
+        }
+        if mines == 0 || size.0 * size.1 / 2 < mines {
+            return Err("Mine density must be greater than 0% and no more than 50%");
+        }
+        if wrap && topology == Topology::Hex && size.0 % 2 == 1 {
+            return Err("A wrapping hexagonal board must have an even height");
+        }
+        let mut board = Board::make_empty(size);
+        board.topology = topology;
+        board.wrap = wrap;
+        board.mines_per_tile = mines_per_tile;
+        // The first click clears a whole neighborhood, so there must be room
+        // for every mine outside of it.
+        let largest_neighborhood = board
+            .tiles
+            .indexed_iter()
+            .map(|(pos, _)| board.neighbor_coords(pos).count())
+            .max()
+            .unwrap_or(0);
+        let capacity = mines_per_tile as usize;
+        if size.0 * size.1 * capacity < mines + largest_neighborhood * capacity {
+            return Err("There are too many mines to keep the first click safe");
+        }
+        board.seed = seed;
+        board.rng = StdRng::seed_from_u64(seed);
+        for _ in 0..mines {
+            board.place_mine();
+        }
+        Ok(board)
+    }
+
+    /// Make a board with mines at specific locations, such as one recorded in a
+    /// replay. A location that appears more than once holds that many mines.
+    /// Return an error if a location is outside the board or holds more than
+    /// `mines_per_tile` mines.
+    pub fn make_from_mines(
+        size: (usize, usize),
+        topology: Topology,
+        wrap: bool,
+        mines: &[Pos],
+        mines_per_tile: u8,
+        seed: u64,
+    ) -> Result<Board, &'static str> {
+        let mut board = Board::make_empty(size);
+        board.topology = topology;
+        board.wrap = wrap;
+        board.mines_per_tile = mines_per_tile;
+        board.seed = seed;
+        board.rng = StdRng::seed_from_u64(seed);
+        for &pos in mines {
+            if board.tiles.get(pos).is_none() {
+                return Err("A mine is outside the board");
+            }
+            if !board.place_mine_at(pos) {
+                return Err("A tile has more mines than it can hold");
+            }
+        }
+        Ok(board)
+    }
+
+    /// Ensure that a particular starting place on the board is safe. Assumes
+    /// that there is sufficient empty space in the board to relocate mines.
+    pub fn ensure_safe_start(&mut self, start: Pos) {
+        // Relocating mines once does not actually _ensure_ safety, so just keep
+        // doing it until the mines have all gone elsewhere.
+        loop {
+            match self.tiles[start] {
+                // Safe start! Return.
+                Tile::Safe(0) => return,
+                // Remove mines from this square and adjacent ones.
+                _ => {
+                    for neighbor_pos in self.neighbor_coords(start).collect::<Vec<_>>() {
+                        self.relocate_mines(neighbor_pos);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Ensure that a particular starting place on the board is safe, and that
+    /// the whole board can be solved from there by pure logic, without any
+    /// guessing. Return false if no such layout could be found within the
+    /// number of attempts, in which case the start is still safe but the board may
+    /// require guessing.
+    pub fn ensure_no_guess_start(&mut self, start: Pos) -> bool {
+        for _ in 0..NO_GUESS_ATTEMPTS {
+            self.ensure_safe_start(start);
+            if self.is_solvable_from(start) {
+                return true;
+            }
+            self.shuffle_mines();
+        }
+        self.ensure_safe_start(start);
+        false
+    }
+
+    /// Return true if the solver can uncover every safe tile on the board
+    /// after starting at a particular place, without guessing.
+    pub fn is_solvable_from(&self, start: Pos) -> bool {
+        let mut board = self.clone();
+        board.tilestates.fill(TileState::Hidden);
+        board.flags_left = self.count_mines() as isize;
+        board.safe_squares_left = (self.tiles.len() - self.count_mined_tiles()) as isize;
+        board.reveal(start);
+        let mut cache = EnumerationCache::default();
+        while board.safe_squares_left > 0 {
+            let deductions = solver::solve_with_cache(&board, &mut cache);
+            if deductions.safe.is_empty() {
+                return false;
+            }
+            for pos in deductions.safe {
+                board.reveal(pos);
+            }
+        }
+        true
+    }
+
+    /// Return the total number of mines on the board.
+    fn count_mines(&self) -> usize {
+        self.tiles.iter().map(|t| t.get_mines() as usize).sum()
+    }
+
+    /// Return the number of tiles on the board that hold at least one mine.
+    fn count_mined_tiles(&self) -> usize {
+        self.tiles.iter().filter(|tile| tile.is_mine()).count()
+    }
+
+    /// Move every mine on the board to a new random location.
+    fn shuffle_mines(&mut self) {
+        let mines = self.count_mines();
+        let mined_tiles = self.count_mined_tiles();
+        self.tiles.fill(Tile::Safe(0));
+        self.flags_left -= mines as isize;
+        self.safe_squares_left += mined_tiles as isize;
+        for _ in 0..mines {
+            self.place_mine();
+        }
+    }
+
+    /// Place a mine on a board. Assumes that there is sufficient empty space to
+    /// place a mine.
+    fn place_mine(&mut self) {
+        // Keep trying to place the mine until successful.
+        loop {
+            let (h, w) = self.size;
+            let y = self.rng.gen_range(0, h);
+            let x = self.rng.gen_range(0, w);
+            if self.place_mine_at((y, x)) {
+                break;
+            }
+        }
+    }
+
+    /// Place a mine at a specific location on the board. Return true if the
+    /// mine was placed, and false if the tile already holds as many mines as it
+    /// can.
+    fn place_mine_at(&mut self, pos: Pos) -> bool {
+        self.tiles[pos] = match self.tiles[pos] {
+            Tile::Safe(_) => {
+                self.safe_squares_left -= 1;
+                Tile::Mine(1)
+            }
+            Tile::Mine(n) if n < self.mines_per_tile => Tile::Mine(n + 1),
+            Tile::Mine(_) => return false,
+        };
+        for neighbor_pos in self.neighbor_coords(pos).collect::<Vec<_>>() {
+            if let Tile::Safe(n) = &mut self.tiles[neighbor_pos] {
+                *n += 1
+            }
+        }
+        self.flags_left += 1;
+        true
+    }
+
+    /// Remove every mine from a specific location on the board. Return the
+    /// number of mines removed, which is zero if there were none initially.
+    fn remove_mines(&mut self, pos: Pos) -> u8 {
+        // If this square is already safe, do nothing.
+        let removed = match self.tiles[pos] {
+            Tile::Mine(n) => n,
+            Tile::Safe(_) => return 0,
+        };
+        // Reset this square so that it is not counted in the number of mines.
+        // Use the number of mines removed to prevent underflow (since this
+        // cell itself is included in the neighbor iterator).
+        self.tiles[pos] = Tile::Safe(removed);
+        let mut mine_count = 0;
+        for neighbor_pos in self.neighbor_coords(pos).collect::<Vec<_>>() {
+            match &mut self.tiles[neighbor_pos] {
+                // Decrement each adjacent square.
+                Tile::Safe(n) => *n -= removed,
+                // Add the mines adjacent to this square.
+                Tile::Mine(n) => mine_count += *n,
+            }
+        }
+        self.tiles[pos] = Tile::Safe(mine_count);
+        self.flags_left -= removed as isize;
+        self.safe_squares_left += 1;
+        removed
+    }
+
+    /// Remove the mines from a tile and replace them somewhere on the board
+    /// (could be the same spot!).
+    fn relocate_mines(&mut self, pos: Pos) {
+        for _ in 0..self.remove_mines(pos) {
+            self.place_mine()
+        }
+    }
+
+    /// Return an iterator of the coordinates of the tiles surrounding a
+    /// square, including the square itself.
+    pub fn neighbor_coords(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.topology.neighbor_coords(pos, self.size, self.wrap)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Difficulty {
+    pub size: (usize, usize),
+    pub mines: usize,
+    #[serde(default)]
+    pub topology: Topology,
+    /// Whether the edges of the board wrap around.
+    #[serde(default)]
+    pub wrap: bool,
+    /// The largest number of mines that a single tile can hold.
+    #[serde(default = "one_mine_per_tile")]
+    pub mines_per_tile: u8,
+}
+
+impl Difficulty {
+    pub fn beginner() -> Difficulty {
+        Difficulty {
+            size: (9, 9),
+            mines: 10,
+            topology: Topology::Square,
+            wrap: false,
+            mines_per_tile: 1,
+        }
+    }
+    pub fn intermediate() -> Difficulty {
+        Difficulty {
+            size: (16, 16),
+            mines: 40,
+            topology: Topology::Square,
+            wrap: false,
+            mines_per_tile: 1,
+        }
+    }
+    pub fn expert() -> Difficulty {
+        Difficulty {
+            size: (16, 30),
+            mines: 99,
+            topology: Topology::Square,
+            wrap: false,
+            mines_per_tile: 1,
+        }
+    }
+
+    /// Make a new random board with a preset size and number of mines based on
+    /// the given difficulty.
+    pub fn new_game(&self, seed: u64) -> Result<Board, &'static str> {
+        Board::make_random(
+            self.size,
+            self.topology.clone(),
+            self.wrap,
+            self.mines,
+            self.mines_per_tile,
+            seed,
+        )
+    }
+
+    /// Return the name of the difficulty, or a description of its size and
+    /// mine count if it is not one of the presets.
+    pub fn get_name(&self) -> String {
+        let square = Difficulty {
+            topology: Topology::Square,
+            wrap: false,
+            mines_per_tile: 1,
+            ..self.clone()
+        };
+        let name = if square == Difficulty::beginner() {
+            "Beginner".to_owned()
+        } else if square == Difficulty::intermediate() {
+            "Intermediate".to_owned()
+        } else if square == Difficulty::expert() {
+            "Expert".to_owned()
+        } else {
+            format!("{}x{}, {} mines", self.size.1, self.size.0, self.mines)
+        };
+        let name = match self.topology.get_name() {
+            Some(topology_name) => format!("{} ({})", name, topology_name),
+            None => name,
+        };
+        let name = if self.wrap {
+            format!("{} (wrapping)", name)
+        } else {
+            name
+        };
+        if 1 < self.mines_per_tile {
+            format!("{} (up to {} per tile)", name, self.mines_per_tile)
+        } else {
+            name
+        }
+    }
+
+    pub fn with_density((h, w): (usize, usize), density: f32) -> Difficulty {
+        Difficulty {
+            size: (h, w),
+            mines: ((h * w) as f32 * density).round() as usize,
+            topology: Topology::Square,
+            wrap: false,
+            mines_per_tile: 1,
+        }
+    }
+}
+
+fn unseeded_rng() -> StdRng {
+    StdRng::seed_from_u64(0)
+}
+
+fn one_mine_per_tile() -> u8 {
+    1
+}
+
+/// Pick a random seed for a new board.
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::make_board;
+
+    #[test]
+    fn reads_tiles_saved_before_counts() {
+        let tiles: Vec<Tile> =
+            serde_json::from_str(r#"["Mine", {"Safe": 2}, {"Mine": 3}]"#).unwrap();
+        assert_eq!(tiles, [Tile::Mine(1), Tile::Safe(2), Tile::Mine(3)]);
+        let tilestates: Vec<TileState> =
+            serde_json::from_str(r#"["Hidden", "Flagged", {"Flagged": 2}, "Uncovered"]"#).unwrap();
+        assert_eq!(
+            tilestates,
+            [
+                TileState::Hidden,
+                TileState::Flagged(1),
+                TileState::Flagged(2),
+                TileState::Uncovered,
+            ]
+        );
+    }
+
+    #[test]
+    fn solvable_when_the_numbers_decide_every_tile() {
+        let board = make_board((3, 3), &[(0, 0)]);
+        assert!(board.is_solvable_from((2, 2)));
+    }
+
+    #[test]
+    fn not_solvable_with_a_fifty_fifty() {
+        // The two tiles on the right each have a mine with the same chance.
+        let board = make_board((2, 3), &[(0, 2)]);
+        assert!(!board.is_solvable_from((1, 0)));
+    }
+
+    #[test]
+    fn is_solvable_from_does_not_change_the_board() {
+        let board = make_board((3, 3), &[(0, 0)]);
+        board.is_solvable_from((2, 2));
+        assert!(board.tilestates.iter().all(|&t| t == TileState::Hidden));
+        assert_eq!(board.safe_squares_left, 8);
+    }
+
+    #[test]
+    fn no_guess_start_is_solvable_for_fixed_seeds() {
+        for seed in 0..5 {
+            let mut board = Difficulty::intermediate().new_game(seed).unwrap();
+            let start = (8, 8);
+            assert!(board.ensure_no_guess_start(start));
+            assert_eq!(board.tiles[start], Tile::Safe(0));
+            assert_eq!(board.count_mines(), 40);
+            assert!(board.is_solvable_from(start));
+        }
+    }
+
+    #[test]
+    fn no_guess_start_is_the_same_for_the_same_seed() {
+        let mut a = Difficulty::expert().new_game(42).unwrap();
+        let mut b = Difficulty::expert().new_game(42).unwrap();
+        assert!(a.ensure_no_guess_start((8, 15)));
+        assert!(b.ensure_no_guess_start((8, 15)));
+        assert_eq!(a.tiles, b.tiles);
+    }
+
+    #[test]
+    fn make_from_mines_rejects_bad_mines() {
+        let make =
+            |mines: &[Pos]| Board::make_from_mines((3, 3), Topology::Square, false, mines, 2, 0);
+        assert!(make(&[(0, 0), (0, 0)]).is_ok());
+        assert!(make(&[(0, 0), (0, 0), (0, 0)]).is_err());
+        assert!(make(&[(3, 0)]).is_err());
+        assert!(make(&[(0, 3)]).is_err());
+    }
+}
@@ -0,0 +1,516 @@
+use serde::{Deserialize, Serialize};
+
+use crate::board::{Board, Pos, Tile, TileState};
+use crate::history::History;
+use crate::solver::{self, Hint};
+use crate::stats::Clicks;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GameStage {
+    Pre,
+    Playing,
+    Exploded,
+    Complete,
+}
+
+/// A single action from the player.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    LeftClick(Pos),
+    RightClick(Pos),
+    /// A left click on an uncovered number, which reveals its neighbors.
+    Chord(Pos),
+    Undo,
+    Redo,
+}
+
+impl Action {
+    /// Return the tile that this action happened at, if any.
+    pub fn get_pos(&self) -> Option<Pos> {
+        match *self {
+            Action::LeftClick(pos) | Action::RightClick(pos) | Action::Chord(pos) => Some(pos),
+            Action::Undo | Action::Redo => None,
+        }
+    }
+}
+
+/// What happens to the mines when the first tile is uncovered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FirstClick {
+    /// Move mines away so that the first click opens an area.
+    Safe,
+    /// Rearrange the mines until the board can be solved from the first click
+    /// without guessing.
+    NoGuess,
+    /// Leave the mines where they are, such as when replaying a game.
+    AsIs,
+}
+
+/// Options that change how actions affect the board.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rules {
+    pub first_click: FirstClick,
+    /// Whether right clicking a flag turns it into a question mark, rather
+    /// than removing it.
+    pub question_marks: bool,
+    /// The number of mines that can be uncovered before the game is lost. With
+    /// more than one, a mine that costs a life stays uncovered and the game
+    /// goes on.
+    pub lives: usize,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            first_click: FirstClick::Safe,
+            question_marks: true,
+            lives: 1,
+        }
+    }
+}
+
+/// A game in progress.
+#[derive(Clone, Debug)]
+pub struct Game {
+    board: Board,
+    stage: GameStage,
+    rules: Rules,
+    history: History,
+    clicks: Clicks,
+    /// The number of hints given during this game.
+    hints: usize,
+    /// Whether the player has had help (such as undo or hints) during this
+    /// game.
+    assisted: bool,
+    /// Whether the mines could not be arranged so that the board can be solved
+    /// without guessing.
+    no_guess_failed: bool,
+    /// Whether the mines have been arranged for the first click, either by the
+    /// first click itself or by `set_start_board`. Undoing back to before the
+    /// first click leaves them where they are, so every click (and the replay,
+    /// which records the final layout) sees the same layout.
+    mines_placed: bool,
+}
+
+impl Game {
+    /// Start a new game on a board.
+    pub fn new(board: Board, rules: Rules) -> Self {
+        Self::resume(board, GameStage::Pre, rules, false, Clicks::new())
+    }
+
+    /// Continue a game (such as one that was saved) from a board that is
+    /// already in progress. Actions from before this cannot be undone.
+    pub fn resume(
+        board: Board,
+        stage: GameStage,
+        rules: Rules,
+        assisted: bool,
+        clicks: Clicks,
+    ) -> Self {
+        Self {
+            board,
+            stage,
+            rules,
+            history: History::new(),
+            clicks,
+            hints: 0,
+            assisted,
+            no_guess_failed: false,
+            mines_placed: stage != GameStage::Pre,
+        }
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+    pub fn get_stage(&self) -> GameStage {
+        self.stage
+    }
+    pub fn get_rules(&self) -> Rules {
+        self.rules
+    }
+    pub fn get_clicks(&self) -> Clicks {
+        self.clicks
+    }
+    pub fn get_hints(&self) -> usize {
+        self.hints
+    }
+    pub fn is_assisted(&self) -> bool {
+        self.assisted
+    }
+    pub fn did_no_guess_fail(&self) -> bool {
+        self.no_guess_failed
+    }
+
+    /// Return a copy of the board and the tile to start from, if an action is
+    /// the first click of a game whose board must be solvable without
+    /// guessing. Arranging the mines can take a few seconds, so front-ends
+    /// can do it on another thread with `Board::ensure_no_guess_start` and
+    /// pass the result to `set_start_board` before applying the action.
+    pub fn get_no_guess_start(&self, action: Action) -> Option<(Board, Pos)> {
+        match action {
+            Action::LeftClick(pos)
+                if self.stage == GameStage::Pre
+                    && self.rules.first_click == FirstClick::NoGuess
+                    && !self.mines_placed =>
+            {
+                Some((self.board.clone(), pos))
+            }
+            _ => None,
+        }
+    }
+
+    /// Replace the board of a game that has not started with one whose mines
+    /// have already been arranged for the first click, along with whether it
+    /// can be solved without guessing.
+    pub fn set_start_board(&mut self, board: Board, solvable: bool) {
+        if self.stage == GameStage::Pre {
+            self.board = board;
+            self.no_guess_failed = !solvable;
+            self.mines_placed = true;
+        }
+    }
+
+    /// Return the number of mines that can still be uncovered before the game
+    /// is lost, which is zero once it has been lost. Each mine costs a life, so
+    /// uncovering a tile with more than one mine costs more than one life.
+    pub fn get_lives_left(&self) -> usize {
+        let tilestates = self.board.get_tilestates();
+        let uncovered_mines: usize = self
+            .board
+            .get_tiles()
+            .indexed_iter()
+            .filter(|&(pos, _)| tilestates[pos] == TileState::Uncovered)
+            .map(|(_, tile)| tile.get_mines() as usize)
+            .sum();
+        self.rules.lives.saturating_sub(uncovered_mines)
+    }
+
+    /// Return whether the game has been won or lost.
+    pub fn is_over(&self) -> bool {
+        match self.stage {
+            GameStage::Pre | GameStage::Playing => false,
+            GameStage::Exploded | GameStage::Complete => true,
+        }
+    }
+
+    /// Return the action that a left click on a tile would be, which is a
+    /// chord if the tile is already uncovered.
+    pub fn get_left_click_action(&self, pos: Pos) -> Action {
+        if self.board.get_tilestates()[pos] == TileState::Uncovered {
+            Action::Chord(pos)
+        } else {
+            Action::LeftClick(pos)
+        }
+    }
+
+    /// Apply an action and return the coordinates of the tiles that changed
+    /// state, or return `None` if the action is not allowed right now. Clicks
+    /// are not allowed once the game is over, chords are only allowed on
+    /// uncovered tiles, and undo and redo are not allowed if there is nothing
+    /// to undo or redo. Undo marks the game as assisted.
+    pub fn apply(&mut self, action: Action) -> Option<Vec<Pos>> {
+        match action {
+            Action::Undo => {
+                let (stage, changed) = self.history.undo(&mut self.board)?;
+                self.stage = stage;
+                self.assisted = true;
+                Some(changed)
+            }
+            Action::Redo => {
+                let (stage, changed) = self.history.redo(&mut self.board)?;
+                self.stage = stage;
+                Some(changed)
+            }
+            _ if self.is_over() => None,
+            Action::LeftClick(pos) => Some(self.left_click(pos)),
+            Action::Chord(pos) if self.board.get_tilestates()[pos] == TileState::Uncovered => {
+                Some(self.left_click(pos))
+            }
+            Action::Chord(_) => None,
+            Action::RightClick(pos) => Some(self.right_click(pos)),
+        }
+    }
+
+    /// Find a tile that can be proven to be safe or a mine from the numbers
+    /// that are uncovered, or return `None` if there is none or the game is
+    /// not in progress. Giving a hint marks the game as assisted. The solver
+    /// assumes that each tile holds at most one mine, so there are no hints
+    /// on boards where a tile can hold more.
+    pub fn hint(&mut self) -> Option<Hint> {
+        if self.stage != GameStage::Playing || 1 < self.board.get_mines_per_tile() {
+            return None;
+        }
+        let hint = solver::find_hint(&self.board)?;
+        self.hints += 1;
+        self.assisted = true;
+        Some(hint)
+    }
+
+    /// Mark the game as assisted, such as when an assist mode makes a move for
+    /// the player.
+    pub fn mark_assisted(&mut self) {
+        self.assisted = true;
+    }
+
+    /// Return a right click that flags a hidden tile next to a number whose
+    /// covered neighbors must all be mines, or `None` if there is no such
+    /// tile. Flags placed by the player are trusted. This is never possible
+    /// when a tile can hold more than one mine, since a number no longer says
+    /// how many of its neighbors are mines.
+    pub fn find_auto_flag(&self) -> Option<Action> {
+        if 1 < self.board.get_mines_per_tile() {
+            return None;
+        }
+        self.find_around_numbers(|_, n, covered, _| {
+            if covered.len() != n as usize {
+                return None;
+            }
+            let tilestates = self.board.get_tilestates();
+            covered
+                .iter()
+                .find(|&&pos| tilestates[pos] == TileState::Hidden)
+                .map(|&pos| Action::RightClick(pos))
+        })
+    }
+
+    /// Return a chord on a number that already has as many flags around it as
+    /// its value and still has other covered neighbors that are not flagged,
+    /// or `None` if there is no such number.
+    pub fn find_auto_chord(&self) -> Option<Action> {
+        let tilestates = self.board.get_tilestates();
+        self.find_around_numbers(|pos, n, covered, flags| {
+            if flags == n as usize
+                && covered
+                    .iter()
+                    .any(|&neighbor_pos| tilestates[neighbor_pos].get_flags() == 0)
+            {
+                Some(Action::Chord(pos))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Call a function with each uncovered number, its value (less any mines
+    /// around it that are uncovered, which are already known), its covered
+    /// neighbors, and the number of flags on those, and return the
+    /// first action it returns. Return `None` if the game is not in progress.
+    fn find_around_numbers(
+        &self,
+        f: impl Fn(Pos, u8, &[Pos], usize) -> Option<Action>,
+    ) -> Option<Action> {
+        if self.stage != GameStage::Playing {
+            return None;
+        }
+        let tilestates = self.board.get_tilestates();
+        for (pos, tilestate) in tilestates.indexed_iter() {
+            if *tilestate != TileState::Uncovered {
+                continue;
+            }
+            if let Tile::Safe(n) = self.board.get_tiles()[pos] {
+                let uncovered_mines: u8 = self
+                    .board
+                    .neighbor_coords(pos)
+                    .filter(|&neighbor_pos| tilestates[neighbor_pos] == TileState::Uncovered)
+                    .map(|neighbor_pos| self.board.get_tiles()[neighbor_pos].get_mines())
+                    .sum();
+                let n = n.saturating_sub(uncovered_mines);
+                let covered: Vec<Pos> = self
+                    .board
+                    .neighbor_coords(pos)
+                    .filter(|&neighbor_pos| tilestates[neighbor_pos] != TileState::Uncovered)
+                    .collect();
+                let flags = covered
+                    .iter()
+                    .map(|&neighbor_pos| tilestates[neighbor_pos].get_flags() as usize)
+                    .sum();
+                if let Some(action) = f(pos, n, &covered, flags) {
+                    return Some(action);
+                }
+            }
+        }
+        None
+    }
+
+    /// Apply a left click to a tile (which is a chord, if the tile is already
+    /// uncovered), starting the game if it has not started yet. The mines are
+    /// only arranged the first time the game starts.
+    fn left_click(&mut self, pos: Pos) -> Vec<Pos> {
+        let tilestates_before = self.board.get_tilestates().clone();
+        let stage_before = self.stage;
+        self.clicks.add(self.get_left_click_action(pos));
+        if self.stage == GameStage::Pre {
+            match self.rules.first_click {
+                _ if self.mines_placed => (),
+                FirstClick::Safe => self.board.ensure_safe_start(pos),
+                FirstClick::NoGuess => {
+                    self.no_guess_failed = !self.board.ensure_no_guess_start(pos);
+                }
+                FirstClick::AsIs => (),
+            }
+            self.mines_placed = true;
+            self.stage = GameStage::Playing;
+        }
+        let lives_before = self.get_lives_left();
+        let updated_squares = self.board.left_click(pos);
+        let lives_after = self.get_lives_left();
+        if lives_after == 0 {
+            self.stage = GameStage::Exploded;
+        } else if lives_after < lives_before {
+            // Surviving a mine is a second chance, like undo.
+            self.assisted = true;
+        }
+        if self.stage == GameStage::Playing && self.board.get_safe_squares_left() == 0 {
+            self.stage = GameStage::Complete;
+        }
+        self.history.record(
+            &self.board,
+            &tilestates_before,
+            &updated_squares,
+            stage_before,
+            self.stage,
+        );
+        updated_squares
+    }
+
+    /// Apply a right click to a tile.
+    fn right_click(&mut self, pos: Pos) -> Vec<Pos> {
+        let tilestates_before = self.board.get_tilestates().clone();
+        self.clicks.add(Action::RightClick(pos));
+        let updated_squares = self.board.right_click(pos, self.rules.question_marks);
+        self.history.record(
+            &self.board,
+            &tilestates_before,
+            &updated_squares,
+            self.stage,
+            self.stage,
+        );
+        updated_squares
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Difficulty;
+    use crate::test_utils::{make_board, make_game};
+
+    #[test]
+    fn first_click_uses_a_prepared_no_guess_board() {
+        let rules = Rules {
+            first_click: FirstClick::NoGuess,
+            ..Rules::default()
+        };
+        let mut game = Game::new(Difficulty::beginner().new_game(7).unwrap(), rules);
+        let action = Action::LeftClick((4, 4));
+        let (mut board, start) = game.get_no_guess_start(action).unwrap();
+        assert!(board.ensure_no_guess_start(start));
+        let tiles = board.get_tiles().clone();
+        game.set_start_board(board, true);
+        assert!(game.get_no_guess_start(action).is_none());
+        game.apply(action);
+        assert_eq!(game.get_board().get_tiles(), &tiles);
+        assert_eq!(game.get_stage(), GameStage::Playing);
+        assert!(!game.did_no_guess_fail());
+    }
+
+    #[test]
+    fn first_click_after_undoing_it_keeps_the_mines() {
+        let mut game = Game::new(Difficulty::expert().new_game(3).unwrap(), Rules::default());
+        game.apply(Action::LeftClick((8, 15)));
+        let tiles = game.get_board().get_tiles().clone();
+        game.apply(Action::Undo);
+        assert_eq!(game.get_stage(), GameStage::Pre);
+        game.apply(Action::LeftClick((0, 0)));
+        assert_eq!(game.get_board().get_tiles(), &tiles);
+    }
+
+    /// A 3x7 board with mines in the top corners.
+    fn make_corner_board() -> Board {
+        make_board((3, 7), &[(0, 0), (0, 6)])
+    }
+
+    #[test]
+    fn left_click_starts_the_game_and_opens_an_area() {
+        let mut game = make_game(make_corner_board(), 1);
+        let changed = game.apply(Action::LeftClick((2, 3))).unwrap();
+        assert_eq!(game.get_stage(), GameStage::Complete);
+        assert_eq!(changed.len(), 19);
+        assert_eq!(game.get_clicks().left, 1);
+    }
+
+    #[test]
+    fn clicks_are_not_allowed_once_the_game_is_over() {
+        let mut game = make_game(make_corner_board(), 1);
+        game.apply(Action::LeftClick((0, 0)));
+        assert_eq!(game.get_stage(), GameStage::Exploded);
+        assert_eq!(game.apply(Action::LeftClick((2, 3))), None);
+        assert_eq!(game.apply(Action::RightClick((0, 6))), None);
+    }
+
+    #[test]
+    fn chords_are_only_allowed_on_uncovered_tiles() {
+        let mut game = make_game(make_corner_board(), 1);
+        game.apply(Action::RightClick((0, 0)));
+        assert_eq!(game.apply(Action::Chord((0, 0))), None);
+        assert_eq!(game.apply(Action::Chord((0, 6))), None);
+        assert_eq!(game.get_clicks().chord, 0);
+        game.apply(Action::LeftClick((0, 1)));
+        assert!(game.apply(Action::Chord((0, 1))).is_some());
+        assert_eq!(game.get_clicks().chord, 1);
+    }
+
+    #[test]
+    fn uncovering_a_mine_costs_a_life() {
+        let mut game = make_game(make_corner_board(), 2);
+        game.apply(Action::LeftClick((0, 0)));
+        assert_eq!(game.get_stage(), GameStage::Playing);
+        assert_eq!(game.get_lives_left(), 1);
+        assert!(game.is_assisted());
+        game.apply(Action::LeftClick((0, 6)));
+        assert_eq!(game.get_stage(), GameStage::Exploded);
+        assert_eq!(game.get_lives_left(), 0);
+    }
+
+    #[test]
+    fn each_mine_on_a_tile_costs_a_life() {
+        let mut game = make_game(make_board((1, 3), &[(0, 0), (0, 0)]), 3);
+        game.apply(Action::LeftClick((0, 0)));
+        assert_eq!(game.get_stage(), GameStage::Playing);
+        assert_eq!(game.get_lives_left(), 1);
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_board() {
+        let mut game = make_game(make_corner_board(), 1);
+        assert_eq!(game.apply(Action::Undo), None);
+        game.apply(Action::RightClick((0, 0)));
+        let flagged = game.get_board().get_tilestates().clone();
+        game.apply(Action::LeftClick((0, 1)));
+        game.apply(Action::LeftClick((0, 6)));
+        assert_eq!(game.get_stage(), GameStage::Exploded);
+
+        game.apply(Action::Undo).unwrap();
+        assert_eq!(game.get_stage(), GameStage::Playing);
+        assert!(game.is_assisted());
+        game.apply(Action::Undo).unwrap();
+        assert_eq!(game.get_board().get_tilestates(), &flagged);
+        assert_eq!(game.get_board().get_flags_left(), 1);
+
+        game.apply(Action::Redo).unwrap();
+        game.apply(Action::Redo).unwrap();
+        assert_eq!(game.get_stage(), GameStage::Exploded);
+        assert_eq!(game.apply(Action::Redo), None);
+    }
+
+    #[test]
+    fn a_new_click_after_undo_clears_redo() {
+        let mut game = make_game(make_corner_board(), 1);
+        game.apply(Action::RightClick((0, 0)));
+        game.apply(Action::Undo).unwrap();
+        game.apply(Action::RightClick((0, 6)));
+        assert_eq!(game.apply(Action::Redo), None);
+    }
+}
@@ -0,0 +1,86 @@
+use ndarray::Array2;
+
+use crate::board::{Board, Pos, TileState};
+use crate::game::GameStage;
+
+/// A single player action, recorded so that it can be undone and redone.
+#[derive(Clone, Debug)]
+struct Action {
+    stage_before: GameStage,
+    stage_after: GameStage,
+    /// Each tile that changed, along with its state before and after the
+    /// action.
+    tiles: Vec<(Pos, TileState, TileState)>,
+}
+
+impl Action {
+    /// Return the coordinates of the tiles that changed.
+    fn get_positions(&self) -> Vec<Pos> {
+        self.tiles.iter().map(|&(pos, _, _)| pos).collect()
+    }
+}
+
+/// The actions taken so far in a game, for undo and redo.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    undo_stack: Vec<Action>,
+    redo_stack: Vec<Action>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an action that changed the tiles at the given positions, given
+    /// the state of every tile before the action. This clears anything that
+    /// could be redone.
+    pub fn record(
+        &mut self,
+        board: &Board,
+        tilestates_before: &Array2<TileState>,
+        changed: &[Pos],
+        stage_before: GameStage,
+        stage_after: GameStage,
+    ) {
+        if changed.is_empty() && stage_before == stage_after {
+            return;
+        }
+        let tiles = changed
+            .iter()
+            .map(|&pos| (pos, tilestates_before[pos], board.get_tilestates()[pos]))
+            .collect();
+        self.undo_stack.push(Action {
+            stage_before,
+            stage_after,
+            tiles,
+        });
+        self.redo_stack.clear();
+    }
+
+    /// Undo the most recent action and return the stage that the game was in
+    /// before it along with the tiles that changed, or return `None` if there
+    /// is nothing to undo.
+    pub fn undo(&mut self, board: &mut Board) -> Option<(GameStage, Vec<Pos>)> {
+        let action = self.undo_stack.pop()?;
+        for &(pos, before, _) in action.tiles.iter().rev() {
+            board.set_tilestate(pos, before);
+        }
+        let result = (action.stage_before, action.get_positions());
+        self.redo_stack.push(action);
+        Some(result)
+    }
+
+    /// Redo the most recently undone action and return the stage that the game
+    /// was in after it along with the tiles that changed, or return `None` if
+    /// there is nothing to redo.
+    pub fn redo(&mut self, board: &mut Board) -> Option<(GameStage, Vec<Pos>)> {
+        let action = self.redo_stack.pop()?;
+        for &(pos, _, after) in &action.tiles {
+            board.set_tilestate(pos, after);
+        }
+        let result = (action.stage_after, action.get_positions());
+        self.undo_stack.push(action);
+        Some(result)
+    }
+}
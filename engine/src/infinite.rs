@@ -0,0 +1,431 @@
+//! A board with no edges. Tiles are stored in square chunks, and the mines in
+//! each chunk are only placed once the player uncovers a tile next to it, so
+//! the board can grow in every direction for as long as the game lasts.
+
+use ndarray::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::collections::{HashMap, VecDeque};
+
+use crate::board::{Tile, TileState};
+use crate::game::{GameStage, Rules};
+
+/// The height and width of each chunk of tiles.
+pub const CHUNK_SIZE: usize = 16;
+
+/// The smallest number of mines in each chunk. With fewer mines, a single
+/// click could uncover an area of zeros that never ends.
+pub const MIN_MINES_PER_CHUNK: usize = CHUNK_SIZE * CHUNK_SIZE / 8;
+
+/// The largest number of mines in each chunk.
+pub const MAX_MINES_PER_CHUNK: usize = CHUNK_SIZE * CHUNK_SIZE / 2;
+
+/// The coordinates (row, column) of a tile on an infinite board, which can be
+/// negative. The first tile uncovered is usually near (0, 0).
+pub type InfinitePos = (i64, i64);
+
+/// The tiles in one chunk of an infinite board.
+#[derive(Clone, Debug)]
+struct Chunk {
+    mines: Array2<bool>,
+    tilestates: Array2<TileState>,
+}
+
+/// A game on a board with no edges. The game can only be lost, and the score
+/// is the number of safe tiles uncovered.
+#[derive(Clone, Debug)]
+pub struct InfiniteGame {
+    mines_per_chunk: usize,
+    /// The seed used to generate every chunk.
+    seed: u64,
+    rules: Rules,
+    stage: GameStage,
+    /// The first tile uncovered. No mines are placed on it or around it.
+    start: Option<InfinitePos>,
+    /// Every chunk that has been generated, by its (row, column) among the
+    /// other chunks.
+    chunks: HashMap<InfinitePos, Chunk>,
+    /// The number of safe tiles uncovered.
+    cleared: usize,
+    /// The number of mines uncovered, which is only more than zero before the
+    /// game is lost if it was started with more than one life.
+    mines_uncovered: usize,
+    flags: usize,
+}
+
+impl InfiniteGame {
+    /// Start a new game with a number of mines in each chunk. The first
+    /// click is always safe, and always uncovers an area of zeros.
+    pub fn new(mines_per_chunk: usize, seed: u64, rules: Rules) -> Result<Self, &'static str> {
+        if !(MIN_MINES_PER_CHUNK..=MAX_MINES_PER_CHUNK).contains(&mines_per_chunk) {
+            return Err("Mine density on an infinite board must be from 0.125 to 0.5");
+        }
+        Ok(Self {
+            mines_per_chunk,
+            seed,
+            rules,
+            stage: GameStage::Pre,
+            start: None,
+            chunks: HashMap::new(),
+            cleared: 0,
+            mines_uncovered: 0,
+            flags: 0,
+        })
+    }
+
+    pub fn get_mines_per_chunk(&self) -> usize {
+        self.mines_per_chunk
+    }
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+    pub fn get_rules(&self) -> Rules {
+        self.rules
+    }
+    pub fn get_stage(&self) -> GameStage {
+        self.stage
+    }
+    /// Return the number of safe tiles uncovered, which is the score.
+    pub fn get_cleared(&self) -> usize {
+        self.cleared
+    }
+    /// Return the number of flags on the board.
+    pub fn get_flags(&self) -> usize {
+        self.flags
+    }
+
+    /// Return the number of mines that can still be uncovered before the game
+    /// is lost.
+    pub fn get_lives_left(&self) -> usize {
+        self.rules.lives.saturating_sub(self.mines_uncovered)
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.stage == GameStage::Exploded
+    }
+
+    /// Return the state of a tile. Tiles in chunks that have not been
+    /// generated yet are hidden.
+    pub fn get_tilestate(&self, pos: InfinitePos) -> TileState {
+        let (chunk_pos, local_pos) = split_pos(pos);
+        self.chunks
+            .get(&chunk_pos)
+            .map_or(TileState::Hidden, |chunk| chunk.tilestates[local_pos])
+    }
+
+    /// Return what is on a tile, or `None` if its mines (or the mines around
+    /// it, for a number) have not been placed yet.
+    pub fn get_tile(&self, pos: InfinitePos) -> Option<Tile> {
+        if self.is_mine(pos)? {
+            return Some(Tile::Mine(1));
+        }
+        let mut mines = 0;
+        for neighbor_pos in neighbor_coords(pos) {
+            if self.is_mine(neighbor_pos)? {
+                mines += 1;
+            }
+        }
+        Some(Tile::Safe(mines))
+    }
+
+    /// Handle a left click on a tile and return a (possibly empty) vector of
+    /// coordinates that changed state as a result. The first click decides
+    /// where the mines can go.
+    pub fn left_click(&mut self, pos: InfinitePos) -> Vec<InfinitePos> {
+        if self.is_over() {
+            return vec![];
+        }
+        if self.stage == GameStage::Pre {
+            self.start = Some(pos);
+            self.stage = GameStage::Playing;
+        }
+        match self.get_tilestate(pos) {
+            TileState::Hidden | TileState::QuestionMark => self.reveal(pos),
+            TileState::Flagged(_) => vec![],
+            TileState::Uncovered => self.chord(pos),
+        }
+    }
+
+    /// Cycle the flag and question mark on a tile, skipping the question mark
+    /// if the rules do not allow them. Return a (possibly empty) vector of the
+    /// coordinates of tiles that changed state.
+    pub fn right_click(&mut self, pos: InfinitePos) -> Vec<InfinitePos> {
+        if self.stage != GameStage::Playing {
+            return vec![];
+        }
+        let tilestate = match self.get_tilestate(pos) {
+            TileState::Hidden => {
+                self.flags += 1;
+                TileState::Flagged(1)
+            }
+            TileState::Flagged(_) => {
+                self.flags -= 1;
+                if self.rules.question_marks {
+                    TileState::QuestionMark
+                } else {
+                    TileState::Hidden
+                }
+            }
+            TileState::QuestionMark => TileState::Hidden,
+            TileState::Uncovered => return vec![],
+        };
+        self.set_tilestate(pos, tilestate);
+        vec![pos]
+    }
+
+    /// Uncover the neighbors of an uncovered number that has the right number
+    /// of flags around it. Return a (possibly empty) vector of the coordinates
+    /// of tiles that changed state.
+    pub fn chord(&mut self, pos: InfinitePos) -> Vec<InfinitePos> {
+        if self.stage != GameStage::Playing || self.get_tilestate(pos) != TileState::Uncovered {
+            return vec![];
+        }
+        let n = match self.get_tile(pos) {
+            Some(Tile::Safe(n)) => n,
+            _ => return vec![],
+        };
+        // Uncovered mines count as flagged.
+        let flags = neighbor_coords(pos)
+            .filter(|&neighbor_pos| match self.get_tilestate(neighbor_pos) {
+                TileState::Flagged(_) => true,
+                TileState::Uncovered => self.is_mine(neighbor_pos) == Some(true),
+                _ => false,
+            })
+            .count();
+        let mut result = vec![];
+        if flags == n as usize {
+            for neighbor_pos in neighbor_coords(pos) {
+                if let TileState::Hidden | TileState::QuestionMark =
+                    self.get_tilestate(neighbor_pos)
+                {
+                    result.append(&mut self.reveal(neighbor_pos));
+                }
+                if self.is_over() {
+                    break;
+                }
+            }
+        }
+        result
+    }
+
+    /// Uncover a tile, along with every tile around it if it is a zero (and so
+    /// on). Return the coordinates of the tiles that were uncovered.
+    fn reveal(&mut self, pos: InfinitePos) -> Vec<InfinitePos> {
+        let mut result = vec![];
+        let mut queue = VecDeque::new();
+        queue.push_back(pos);
+        while let Some(pos) = queue.pop_front() {
+            // A tile can be queued more than once by different zeros.
+            if self.get_tilestate(pos) == TileState::Uncovered {
+                continue;
+            }
+            let tile = self.generate_tile(pos);
+            self.set_tilestate(pos, TileState::Uncovered);
+            result.push(pos);
+            match tile {
+                Tile::Mine(n) => {
+                    self.mines_uncovered += n as usize;
+                    if self.get_lives_left() == 0 {
+                        self.stage = GameStage::Exploded;
+                    }
+                }
+                Tile::Safe(n) => {
+                    self.cleared += 1;
+                    if n == 0 {
+                        queue.extend(neighbor_coords(pos).filter(|&neighbor_pos| {
+                            self.get_tilestate(neighbor_pos) == TileState::Hidden
+                        }));
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Set the state of a tile, generating its chunk if needed.
+    fn set_tilestate(&mut self, pos: InfinitePos, tilestate: TileState) {
+        let (chunk_pos, local_pos) = split_pos(pos);
+        self.generate_chunk(chunk_pos);
+        self.chunks.get_mut(&chunk_pos).unwrap().tilestates[local_pos] = tilestate;
+    }
+
+    /// Return what is on a tile, generating the chunks around it if needed.
+    fn generate_tile(&mut self, pos: InfinitePos) -> Tile {
+        for neighbor_pos in neighbor_coords(pos).chain(std::iter::once(pos)) {
+            self.generate_chunk(split_pos(neighbor_pos).0);
+        }
+        self.get_tile(pos).unwrap()
+    }
+
+    /// Return whether there is a mine on a tile, or `None` if its chunk has not
+    /// been generated yet.
+    fn is_mine(&self, pos: InfinitePos) -> Option<bool> {
+        let (chunk_pos, local_pos) = split_pos(pos);
+        self.chunks
+            .get(&chunk_pos)
+            .map(|chunk| chunk.mines[local_pos])
+    }
+
+    /// Place the mines in a chunk, if they have not been placed yet. Each
+    /// chunk has its own random number generator, so the mines in a chunk
+    /// only depend on the seed and the first click, not on the order in which
+    /// the chunks were generated.
+    fn generate_chunk(&mut self, chunk_pos: InfinitePos) {
+        if self.chunks.contains_key(&chunk_pos) {
+            return;
+        }
+        let (top, left) = (
+            chunk_pos.0 * CHUNK_SIZE as i64,
+            chunk_pos.1 * CHUNK_SIZE as i64,
+        );
+        let start = self.start;
+        let candidates: Vec<(usize, usize)> = (0..CHUNK_SIZE)
+            .flat_map(|y| (0..CHUNK_SIZE).map(move |x| (y, x)))
+            .filter(|&(y, x)| {
+                let pos = (top + y as i64, left + x as i64);
+                !matches!(start, Some(start) if is_neighbor(pos, start))
+            })
+            .collect();
+        let mut rng = StdRng::seed_from_u64(chunk_seed(self.seed, chunk_pos));
+        let mut mines = Array2::from_elem((CHUNK_SIZE, CHUNK_SIZE), false);
+        for &local_pos in candidates.choose_multiple(&mut rng, self.mines_per_chunk) {
+            mines[local_pos] = true;
+        }
+        self.chunks.insert(
+            chunk_pos,
+            Chunk {
+                mines,
+                tilestates: Array2::from_elem((CHUNK_SIZE, CHUNK_SIZE), TileState::Hidden),
+            },
+        );
+    }
+}
+
+/// Split the coordinates of a tile into the coordinates of its chunk and its
+/// coordinates within the chunk.
+fn split_pos((y, x): InfinitePos) -> (InfinitePos, (usize, usize)) {
+    let size = CHUNK_SIZE as i64;
+    (
+        (y.div_euclid(size), x.div_euclid(size)),
+        (y.rem_euclid(size) as usize, x.rem_euclid(size) as usize),
+    )
+}
+
+/// Return an iterator of the coordinates of the eight tiles surrounding a
+/// tile.
+fn neighbor_coords((y, x): InfinitePos) -> impl Iterator<Item = InfinitePos> {
+    (-1..=1)
+        .flat_map(move |dy| (-1..=1).map(move |dx| (y + dy, x + dx)))
+        .filter(move |&pos| pos != (y, x))
+}
+
+/// Return whether two tiles are the same tile or neighbors.
+fn is_neighbor(a: InfinitePos, b: InfinitePos) -> bool {
+    (a.0 - b.0).abs() <= 1 && (a.1 - b.1).abs() <= 1
+}
+
+/// Return the seed for the random number generator of one chunk.
+fn chunk_seed(seed: u64, (y, x): InfinitePos) -> u64 {
+    seed ^ (y as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (x as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_game(seed: u64) -> InfiniteGame {
+        InfiniteGame::new(MIN_MINES_PER_CHUNK, seed, Rules::default()).unwrap()
+    }
+
+    /// Return where the mines are in a chunk, which must have been generated.
+    fn get_mines(game: &InfiniteGame, chunk_pos: InfinitePos) -> Array2<bool> {
+        game.chunks[&chunk_pos].mines.clone()
+    }
+
+    #[test]
+    fn chunks_do_not_depend_on_the_order_they_are_generated_in() {
+        let chunk_positions = [(0, 0), (2, -1), (-3, 4)];
+        let mut game = make_game(7);
+        game.left_click((0, 0));
+        let mut other_game = make_game(7);
+        other_game.left_click((0, 0));
+        for &chunk_pos in &chunk_positions {
+            game.generate_chunk(chunk_pos);
+        }
+        for &chunk_pos in chunk_positions.iter().rev() {
+            other_game.generate_chunk(chunk_pos);
+        }
+        for &chunk_pos in &chunk_positions {
+            assert_eq!(
+                get_mines(&game, chunk_pos),
+                get_mines(&other_game, chunk_pos)
+            );
+            assert_eq!(
+                get_mines(&game, chunk_pos)
+                    .iter()
+                    .filter(|&&mine| mine)
+                    .count(),
+                MIN_MINES_PER_CHUNK
+            );
+        }
+
+        let mut game_with_other_seed = make_game(8);
+        game_with_other_seed.left_click((0, 0));
+        game_with_other_seed.generate_chunk((2, -1));
+        assert_ne!(
+            get_mines(&game, (2, -1)),
+            get_mines(&game_with_other_seed, (2, -1))
+        );
+    }
+
+    #[test]
+    fn first_click_is_safe_and_opens_an_area() {
+        for seed in 0..20 {
+            let mut game = make_game(seed);
+            let start = (seed as i64 * 5, -(seed as i64) * 3);
+            let uncovered = game.left_click(start);
+            assert_eq!(game.get_stage(), GameStage::Playing);
+            assert_eq!(game.get_tile(start), Some(Tile::Safe(0)));
+            assert!(uncovered.len() >= 9);
+            assert_eq!(game.get_cleared(), uncovered.len());
+        }
+    }
+
+    #[test]
+    fn chord_uncovers_the_neighbors_of_a_flagged_number() {
+        let mut game = make_game(3);
+        game.left_click((0, 0));
+        // Find an uncovered number with a hidden safe tile next to it.
+        let hidden_neighbors = |game: &InfiniteGame, pos| -> Vec<InfinitePos> {
+            neighbor_coords(pos)
+                .filter(|&neighbor_pos| game.get_tilestate(neighbor_pos) == TileState::Hidden)
+                .collect()
+        };
+        let size = CHUNK_SIZE as i64;
+        let pos = (-size..size)
+            .flat_map(|y| (-size..size).map(move |x| (y, x)))
+            .find(|&pos| {
+                game.get_tilestate(pos) == TileState::Uncovered
+                    && game.get_tile(pos) != Some(Tile::Safe(0))
+                    && hidden_neighbors(&game, pos)
+                        .iter()
+                        .any(|&neighbor_pos| game.is_mine(neighbor_pos) == Some(false))
+            })
+            .unwrap();
+
+        // Nothing happens until the number has the right number of flags.
+        assert!(game.chord(pos).is_empty());
+        let (mines, safe): (Vec<_>, Vec<_>) = hidden_neighbors(&game, pos)
+            .into_iter()
+            .partition(|&neighbor_pos| game.is_mine(neighbor_pos) == Some(true));
+        for &mine_pos in &mines {
+            game.right_click(mine_pos);
+        }
+        let uncovered = game.chord(pos);
+        assert!(!game.is_over());
+        for safe_pos in safe {
+            assert!(uncovered.contains(&safe_pos));
+            assert_eq!(game.get_tilestate(safe_pos), TileState::Uncovered);
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! The rules of Minesweeper, without any graphics or input handling.
+//!
+//! A `Game` holds a `Board` along with the stage of the game and the history
+//! of actions applied to it. Actions are applied with `Game::apply`, and the
+//! state of the game can be read back at any time.
+
+pub mod board;
+pub mod game;
+mod history;
+pub mod infinite;
+pub mod probability;
+pub mod solver;
+pub mod stats;
+#[cfg(test)]
+mod test_utils;
+pub mod topology;
+
+pub use board::{Board, Difficulty, Pos, Tile, TileState};
+pub use game::{Action, FirstClick, Game, GameStage, Rules};
+pub use infinite::{InfiniteGame, InfinitePos};
+pub use topology::Topology;
@@ -0,0 +1,210 @@
+//! Exact mine probabilities for hidden tiles.
+//!
+//! Every arrangement of mines that agrees with the visible numbers and with
+//! the total number of mines is considered equally likely. Arrangements are
+//! counted per group of frontier tiles (see `solver`), and the tiles away from
+//! the frontier are weighted by the number of ways to fit the remaining mines
+//! among them.
+
+use ndarray::prelude::*;
+
+use crate::board::{Board, TileState};
+use crate::solver::{self, EnumerationCache};
+
+/// Return the probability that each tile on the board is a mine, using only
+/// the information visible to the player. Uncovered tiles have a probability of
+/// zero. Flags placed by the player are not trusted.
+///
+/// Uncovered mines (such as one that cost a life, or the one that exploded) are
+/// known to be mines.
+pub fn mine_probabilities(board: &Board) -> Array2<f64> {
+    let mut cache = EnumerationCache::default();
+    let deductions = solver::solve_with_cache(board, &mut cache);
+    let constraints = solver::get_constraints(board, &deductions);
+    let mut frontier = solver::get_frontier(board, &constraints, &deductions, &mut cache);
+
+    let mut result = Array2::zeros(*board.get_size());
+    for &pos in &deductions.mines {
+        result[pos] = 1.0;
+    }
+
+    // Groups that are too large to enumerate are approximated by treating
+    // their tiles as if they did not border any number.
+    let (groups, skipped): (Vec<_>, Vec<_>) = frontier
+        .groups
+        .drain(..)
+        .partition(|group| group.arrangements.is_some());
+    for group in skipped {
+        frontier.other_tiles.extend(group.tiles);
+    }
+    let others = frontier.other_tiles.len();
+
+    // Scale the number of arrangements in each group so that the largest is 1.
+    // Only the ratios between them matter, and this avoids overflow.
+    let group_weights: Vec<Vec<f64>> = groups
+        .iter()
+        .map(|group| {
+            let totals = &group.arrangements.as_ref().unwrap().totals;
+            let max = totals.iter().copied().max().unwrap_or(0).max(1) as f64;
+            totals.iter().map(|&n| n as f64 / max).collect()
+        })
+        .collect();
+
+    // The weight of each possible number of mines on the whole frontier, based
+    // on the number of ways to place the rest of the mines elsewhere.
+    let max_frontier_mines = groups.iter().map(|group| group.tiles.len()).sum::<usize>();
+    let total_weights: Vec<f64> = match frontier.mines_left {
+        Some(mines_left) => {
+            let ln_weights: Vec<Option<f64>> = (0..=max_frontier_mines)
+                .map(|total| {
+                    if total <= mines_left && mines_left - total <= others {
+                        Some(ln_binomial(others, mines_left - total))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            let max = ln_weights
+                .iter()
+                .filter_map(|&w| w)
+                .fold(f64::NEG_INFINITY, f64::max);
+            ln_weights
+                .iter()
+                .map(|w| w.map_or(0.0, |w| (w - max).exp()))
+                .collect()
+        }
+        // The mine counter is unreliable, so ignore it.
+        None => vec![1.0; max_frontier_mines + 1],
+    };
+
+    let all_groups = group_weights
+        .iter()
+        .fold(vec![1.0], |acc, weights| convolve(&acc, weights));
+    let normalization: f64 = all_groups
+        .iter()
+        .zip(&total_weights)
+        .map(|(a, b)| a * b)
+        .sum();
+    if normalization <= 0.0 {
+        return result;
+    }
+
+    for (i, group) in groups.iter().enumerate() {
+        let arrangements = group.arrangements.as_ref().unwrap();
+        let max = arrangements
+            .totals
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1) as f64;
+        let rest = group_weights
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .fold(vec![1.0], |acc, (_, weights)| convolve(&acc, weights));
+        // The weight of each number of mines in this group, given everything
+        // else on the board.
+        let weights: Vec<f64> = (0..arrangements.totals.len())
+            .map(|k| {
+                rest.iter()
+                    .enumerate()
+                    .map(|(j, w)| w * total_weights.get(k + j).unwrap_or(&0.0))
+                    .sum::<f64>()
+                    / max
+            })
+            .collect();
+        for (t, &pos) in group.tiles.iter().enumerate() {
+            result[pos] = weights
+                .iter()
+                .zip(&arrangements.mines)
+                .map(|(w, mines)| w * mines[t] as f64)
+                .sum::<f64>()
+                / normalization;
+        }
+    }
+
+    if others > 0 {
+        let p = match frontier.mines_left {
+            Some(mines_left) => {
+                all_groups
+                    .iter()
+                    .zip(&total_weights)
+                    .enumerate()
+                    .map(|(total, (a, b))| a * b * mines_left.saturating_sub(total) as f64)
+                    .sum::<f64>()
+                    / normalization
+                    / others as f64
+            }
+            // Fall back to the density of mines on the whole board.
+            None => {
+                let flags = board.get_tilestates().iter();
+                let flags: isize = flags.map(|t| t.get_flags() as isize).sum();
+                let mines = board.get_flags_left() + flags;
+                mines.max(0) as f64 / board.get_tiles().len() as f64
+            }
+        };
+        for &pos in &frontier.other_tiles {
+            result[pos] = p;
+        }
+    }
+
+    for (pos, tilestate) in board.get_tilestates().indexed_iter() {
+        if *tilestate == TileState::Uncovered {
+            result[pos] = 0.0;
+        }
+    }
+    result
+}
+
+/// Return the natural logarithm of the binomial coefficient "n choose k".
+fn ln_binomial(n: usize, k: usize) -> f64 {
+    let k = k.min(n - k);
+    (0..k)
+        .map(|i| ((n - i) as f64).ln() - ((i + 1) as f64).ln())
+        .sum()
+}
+
+/// Given weights for the number of mines in two sets of tiles, return the
+/// weights for the number of mines in both of them combined.
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::make_opened_board;
+
+    #[test]
+    fn known_tiles_are_zero_or_one() {
+        let probabilities = mine_probabilities(&make_opened_board((3, 3), &[(0, 0)], (2, 2)));
+        assert_eq!(probabilities[(0, 0)], 1.0);
+        assert_eq!(probabilities[(2, 2)], 0.0);
+        assert_eq!(probabilities.sum(), 1.0);
+    }
+
+    #[test]
+    fn a_fifty_fifty_is_even() {
+        let probabilities = mine_probabilities(&make_opened_board((2, 3), &[(0, 2)], (1, 0)));
+        assert_eq!(probabilities[(0, 2)], 0.5);
+        assert_eq!(probabilities[(1, 2)], 0.5);
+    }
+
+    #[test]
+    fn tiles_away_from_the_numbers_share_the_other_mines() {
+        // The number at the bottom right touches three hidden tiles, one of
+        // which is a mine, and the other two mines are among the 5 hidden
+        // tiles that it does not touch.
+        let board = make_opened_board((3, 3), &[(0, 0), (0, 2), (1, 1)], (2, 2));
+        let probabilities = mine_probabilities(&board);
+        assert!((probabilities.sum() - 3.0).abs() < 1e-9);
+        assert!((probabilities[(1, 1)] - probabilities[(2, 1)]).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,694 @@
+//! Logical deduction from the information visible to the player.
+//!
+//! The solver uses three kinds of rules, from cheapest to most expensive:
+//!
+//! 1. Single-cell rules: a number whose unknown neighbors are all mines (or
+//!    all safe).
+//! 2. Subset rules: if one number's unknown neighbors are a subset of
+//!    another's, then the leftover tiles hold the difference between them.
+//! 3. Enumeration: every arrangement of mines along the frontier that agrees
+//!    with all of the numbers (and with the total number of mines) is
+//!    considered, and any tile that is the same in all of them is known.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::board::{Board, Pos, Tile, TileState};
+
+/// The maximum number of steps to spend enumerating the arrangements of mines
+/// in a single group of frontier tiles before giving up on that group.
+const MAX_ENUMERATION_STEPS: usize = 1_000_000;
+
+/// Hidden tiles that can be proven to be safe or to be mines.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Deductions {
+    pub safe: BTreeSet<Pos>,
+    pub mines: BTreeSet<Pos>,
+}
+
+impl Deductions {
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.mines.is_empty()
+    }
+}
+
+/// A single tile that can be proven to be safe or to be a mine, along with the
+/// uncovered numbers that prove it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hint {
+    pub pos: Pos,
+    pub is_mine: bool,
+    /// The uncovered numbers that force the tile to be safe or a mine.
+    pub reasons: Vec<Pos>,
+    /// Whether the tile also depends on numbers further away or on the total
+    /// number of mines, rather than only on `reasons`.
+    pub needs_more: bool,
+}
+
+/// A set of unknown tiles, exactly some number of which are mines.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct Constraint {
+    pub tiles: BTreeSet<Pos>,
+    pub mines: usize,
+}
+
+/// A group of frontier tiles that share constraints with each other, but not
+/// with any tile outside the group.
+pub(crate) struct Group {
+    pub tiles: Vec<Pos>,
+    /// Every arrangement of mines among the tiles, or `None` if there were too
+    /// many to enumerate.
+    pub arrangements: Option<Arrangements>,
+}
+
+/// A tally of the arrangements of mines in a group of tiles, indexed by the
+/// number of mines in the arrangement.
+#[derive(Clone)]
+pub(crate) struct Arrangements {
+    /// The number of arrangements with each number of mines.
+    pub totals: Vec<u64>,
+    /// For each number of mines, the number of those arrangements in which
+    /// each tile (in the same order as the group's tiles) is a mine.
+    pub mines: Vec<Vec<u64>>,
+}
+
+/// Everything the player can see that constrains the positions of the mines.
+pub(crate) struct Frontier {
+    pub groups: Vec<Group>,
+    /// Unknown tiles that do not border any uncovered number.
+    pub other_tiles: Vec<Pos>,
+    /// The number of mines among all the unknown tiles, or `None` if the mine
+    /// counter disagrees with what is already known.
+    pub mines_left: Option<usize>,
+}
+
+/// The arrangements of mines in every group that has been enumerated, by the
+/// group's tiles and the constraints on them. Groups far away from the tiles
+/// that were just deduced do not change, so they are only enumerated once.
+#[derive(Default)]
+pub(crate) struct EnumerationCache {
+    groups: HashMap<(Vec<Pos>, Vec<Constraint>), Option<Arrangements>>,
+}
+
+/// Find every hidden tile that is provably safe or provably a mine, using only
+/// the information visible to the player (i.e. the numbers on uncovered
+/// tiles). Flags placed by the player are not trusted.
+pub fn solve(board: &Board) -> Deductions {
+    solve_with_cache(board, &mut EnumerationCache::default())
+}
+
+/// Solve a board like `solve`, reusing the groups enumerated by earlier calls.
+pub(crate) fn solve_with_cache(board: &Board, cache: &mut EnumerationCache) -> Deductions {
+    let mut deductions = Deductions::default();
+    let mut constraints = get_constraints(board, &deductions);
+    loop {
+        reduce_constraints(&mut constraints, &deductions);
+        if apply_single_rules(&constraints, &mut deductions)
+            || apply_subset_rules(&constraints, &mut deductions)
+        {
+            continue;
+        }
+        let frontier = get_frontier(board, &constraints, &deductions, cache);
+        if apply_enumeration(&frontier, &mut deductions) {
+            continue;
+        }
+        return deductions;
+    }
+}
+
+/// Find one hidden tile that is provably safe or provably a mine and that the
+/// player has not already dealt with (i.e. a mine that is not flagged, or a safe
+/// tile that is still covered). Hints that only need one or two numbers to
+/// explain are preferred. Return `None` if nothing can be deduced.
+pub fn find_hint(board: &Board) -> Option<Hint> {
+    let tilestates = board.get_tilestates();
+    let is_useful = |pos: Pos, is_mine: bool| {
+        if is_mine {
+            tilestates[pos].get_flags() == 0
+        } else {
+            tilestates[pos] != TileState::Uncovered
+        }
+    };
+    // Return a hint for one of the tiles if they must all be safe or all be
+    // mines.
+    let hint_from = |tiles: &BTreeSet<Pos>, mines: usize, reasons: Vec<Pos>| {
+        let is_mine = if mines == 0 {
+            false
+        } else if mines == tiles.len() {
+            true
+        } else {
+            return None;
+        };
+        let pos = *tiles.iter().find(|&&pos| is_useful(pos, is_mine))?;
+        Some(Hint {
+            pos,
+            is_mine,
+            reasons,
+            needs_more: false,
+        })
+    };
+    let sources = get_sources(board);
+    for (source, constraint) in &sources {
+        if let Some(hint) = hint_from(&constraint.tiles, constraint.mines, vec![*source]) {
+            return Some(hint);
+        }
+    }
+    for (small_source, small) in &sources {
+        for (big_source, big) in &sources {
+            if small.tiles.len() >= big.tiles.len()
+                || small.mines > big.mines
+                || !small.tiles.is_subset(&big.tiles)
+            {
+                continue;
+            }
+            let difference = big.tiles.difference(&small.tiles).copied().collect();
+            let reasons = vec![*big_source, *small_source];
+            if let Some(hint) = hint_from(&difference, big.mines - small.mines, reasons) {
+                return Some(hint);
+            }
+        }
+    }
+    // Fall back to the full solver, and point to the numbers around the tile.
+    let deductions = solve(board);
+    let safe = deductions.safe.iter().map(|&pos| (pos, false));
+    let mines = deductions.mines.iter().map(|&pos| (pos, true));
+    let (pos, is_mine) = safe
+        .chain(mines)
+        .find(|&(pos, is_mine)| is_useful(pos, is_mine))?;
+    let reasons = board
+        .neighbor_coords(pos)
+        .filter(|&neighbor_pos| tilestates[neighbor_pos] == TileState::Uncovered)
+        .collect();
+    Some(Hint {
+        pos,
+        is_mine,
+        reasons,
+        needs_more: true,
+    })
+}
+
+/// Return each uncovered number that borders at least one tile which is not
+/// uncovered, along with the constraint it puts on those tiles. Flags are not
+/// trusted.
+fn get_sources(board: &Board) -> Vec<(Pos, Constraint)> {
+    let mut sources = vec![];
+    for (pos, tilestate) in board.get_tilestates().indexed_iter() {
+        if *tilestate != TileState::Uncovered {
+            continue;
+        }
+        if let Tile::Safe(n) = board.get_tiles()[pos] {
+            let mut tiles = BTreeSet::new();
+            let mut known_mines = 0;
+            for neighbor_pos in board.neighbor_coords(pos) {
+                if board.get_tilestates()[neighbor_pos] == TileState::Uncovered {
+                    known_mines += board.get_tiles()[neighbor_pos].get_mines() as usize;
+                } else {
+                    tiles.insert(neighbor_pos);
+                }
+            }
+            let n = n as usize;
+            if !tiles.is_empty() && known_mines <= n && n - known_mines <= tiles.len() {
+                sources.push((
+                    pos,
+                    Constraint {
+                        tiles,
+                        mines: n - known_mines,
+                    },
+                ));
+            }
+        }
+    }
+    sources
+}
+
+/// Return a constraint for each uncovered number that borders at least one tile
+/// which is not yet known.
+pub(crate) fn get_constraints(board: &Board, deductions: &Deductions) -> Vec<Constraint> {
+    let mut constraints: Vec<Constraint> = vec![];
+    // Several numbers can put the same constraint on the same tiles.
+    let mut seen = HashSet::new();
+    for (pos, tilestate) in board.get_tilestates().indexed_iter() {
+        if *tilestate != TileState::Uncovered {
+            continue;
+        }
+        if let Tile::Safe(n) = board.get_tiles()[pos] {
+            let mut tiles = BTreeSet::new();
+            let mut known_mines = 0;
+            for neighbor_pos in board.neighbor_coords(pos) {
+                if deductions.mines.contains(&neighbor_pos) {
+                    known_mines += 1;
+                } else if board.get_tilestates()[neighbor_pos] == TileState::Uncovered {
+                    // An uncovered mine is one that cost a life (or lost the
+                    // game).
+                    known_mines += board.get_tiles()[neighbor_pos].get_mines() as usize;
+                } else if board.get_tilestates()[neighbor_pos] != TileState::Uncovered
+                    && !deductions.safe.contains(&neighbor_pos)
+                {
+                    tiles.insert(neighbor_pos);
+                }
+            }
+            // If the numbers contradict each other, then the mine counts are
+            // unreliable and there is nothing to deduce from this one.
+            if tiles.is_empty() || (n as usize) < known_mines {
+                continue;
+            }
+            let constraint = Constraint {
+                tiles,
+                mines: n as usize - known_mines,
+            };
+            if seen.insert(constraint.clone()) {
+                constraints.push(constraint);
+            }
+        }
+    }
+    constraints
+}
+
+/// Remove the tiles that have been deduced from each constraint, along with
+/// the mines among them, and drop the constraints that have no unknown tiles
+/// left. This gives the same constraints as `get_constraints`, without looking
+/// at the whole board again.
+fn reduce_constraints(constraints: &mut Vec<Constraint>, deductions: &Deductions) {
+    *constraints = std::mem::take(constraints)
+        .into_iter()
+        .filter_map(|mut constraint| {
+            let mut known_mines = 0;
+            constraint.tiles.retain(|pos| {
+                if deductions.mines.contains(pos) {
+                    known_mines += 1;
+                    false
+                } else {
+                    !deductions.safe.contains(pos)
+                }
+            });
+            if constraint.tiles.is_empty() || constraint.mines < known_mines {
+                return None;
+            }
+            constraint.mines -= known_mines;
+            Some(constraint)
+        })
+        .collect();
+    constraints.sort_unstable();
+    constraints.dedup();
+}
+
+/// Mark the tiles of a constraint as safe if it has no mines, or as mines if
+/// all of its tiles must be mines. Return true if anything new was deduced.
+fn deduce_from(tiles: &BTreeSet<Pos>, mines: usize, deductions: &mut Deductions) -> bool {
+    let target = if mines == 0 {
+        &mut deductions.safe
+    } else if mines == tiles.len() {
+        &mut deductions.mines
+    } else {
+        return false;
+    };
+    let mut progress = false;
+    for &pos in tiles {
+        progress |= target.insert(pos);
+    }
+    progress
+}
+
+/// Apply the rules that only need a single constraint at a time. Return true if
+/// anything new was deduced.
+fn apply_single_rules(constraints: &[Constraint], deductions: &mut Deductions) -> bool {
+    let mut progress = false;
+    for constraint in constraints {
+        progress |= deduce_from(&constraint.tiles, constraint.mines, deductions);
+    }
+    progress
+}
+
+/// Apply the rules that compare pairs of constraints: if the tiles of one
+/// constraint are a subset of the tiles of another, then the remaining tiles
+/// contain the difference between their mine counts. Return true if anything
+/// new was deduced.
+fn apply_subset_rules(constraints: &[Constraint], deductions: &mut Deductions) -> bool {
+    let mut progress = false;
+    for small in constraints {
+        for big in constraints {
+            if small.tiles.len() >= big.tiles.len()
+                || small.mines > big.mines
+                || !small.tiles.is_subset(&big.tiles)
+            {
+                continue;
+            }
+            let difference = big.tiles.difference(&small.tiles).copied().collect();
+            progress |= deduce_from(&difference, big.mines - small.mines, deductions);
+        }
+    }
+    progress
+}
+
+/// Split the unknown tiles into groups and enumerate the arrangements of mines
+/// in each one.
+pub(crate) fn get_frontier(
+    board: &Board,
+    constraints: &[Constraint],
+    deductions: &Deductions,
+    cache: &mut EnumerationCache,
+) -> Frontier {
+    // Join the tiles of each constraint into the same group.
+    let mut group_ids: BTreeMap<Pos, usize> = BTreeMap::new();
+    let mut group_tiles: Vec<Vec<Pos>> = vec![];
+    for constraint in constraints {
+        let mut ids: Vec<usize> = constraint
+            .tiles
+            .iter()
+            .filter_map(|pos| group_ids.get(pos).copied())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        let id = match ids.first() {
+            Some(&id) => id,
+            None => {
+                group_tiles.push(vec![]);
+                group_tiles.len() - 1
+            }
+        };
+        for &other_id in &ids[1.min(ids.len())..] {
+            let tiles = std::mem::take(&mut group_tiles[other_id]);
+            for &pos in &tiles {
+                group_ids.insert(pos, id);
+            }
+            group_tiles[id].extend(tiles);
+        }
+        for &pos in &constraint.tiles {
+            if group_ids.insert(pos, id).is_none() {
+                group_tiles[id].push(pos);
+            }
+        }
+    }
+    // Every tile of a constraint is in the same group by now.
+    let mut group_constraints = vec![vec![]; group_tiles.len()];
+    for constraint in constraints {
+        let first = constraint.tiles.iter().next().unwrap();
+        group_constraints[group_ids[first]].push(constraint.clone());
+    }
+    let groups = group_tiles
+        .into_iter()
+        .zip(group_constraints)
+        .filter(|(tiles, _)| !tiles.is_empty())
+        .map(|(tiles, constraints)| {
+            let arrangements = enumerate(&tiles, constraints, cache);
+            Group {
+                tiles,
+                arrangements,
+            }
+        })
+        .collect();
+
+    let mut other_tiles = vec![];
+    // Uncovered mines are already left out of the number of flags left.
+    let known_mines = deductions.mines.len();
+    let mut total_mines = board.get_flags_left();
+    for (pos, tilestate) in board.get_tilestates().indexed_iter() {
+        match tilestate {
+            TileState::Uncovered => (),
+            TileState::Flagged(_) | TileState::Hidden | TileState::QuestionMark => {
+                total_mines += tilestate.get_flags() as isize;
+                if !group_ids.contains_key(&pos)
+                    && !deductions.safe.contains(&pos)
+                    && !deductions.mines.contains(&pos)
+                {
+                    other_tiles.push(pos);
+                }
+            }
+        }
+    }
+    let mines_left = if known_mines as isize <= total_mines {
+        Some(total_mines as usize - known_mines)
+    } else {
+        None
+    };
+
+    Frontier {
+        groups,
+        other_tiles,
+        mines_left,
+    }
+}
+
+/// Enumerate every arrangement of mines among a group of tiles that satisfies
+/// the constraints on those tiles, or look it up if the same group has been
+/// enumerated before. Return `None` if this takes too long.
+fn enumerate(
+    tiles: &[Pos],
+    constraints: Vec<Constraint>,
+    cache: &mut EnumerationCache,
+) -> Option<Arrangements> {
+    let key = (tiles.to_vec(), constraints);
+    if let Some(arrangements) = cache.groups.get(&key) {
+        return arrangements.clone();
+    }
+    let indices: BTreeMap<Pos, usize> =
+        tiles.iter().enumerate().map(|(i, &pos)| (pos, i)).collect();
+    let relevant = &key.1;
+    let mut tile_constraints = vec![vec![]; tiles.len()];
+    for (c, constraint) in relevant.iter().enumerate() {
+        for pos in &constraint.tiles {
+            tile_constraints[indices[pos]].push(c);
+        }
+    }
+    let mut enumerator = Enumerator {
+        tile_constraints,
+        needed: relevant.iter().map(|c| c.mines as isize).collect(),
+        unassigned: relevant.iter().map(|c| c.tiles.len() as isize).collect(),
+        assignment: vec![false; tiles.len()],
+        mine_count: 0,
+        steps: 0,
+        arrangements: Arrangements {
+            totals: vec![0; tiles.len() + 1],
+            mines: vec![vec![0; tiles.len()]; tiles.len() + 1],
+        },
+    };
+    let arrangements = if enumerator.assign(0) {
+        Some(enumerator.arrangements)
+    } else {
+        None
+    };
+    cache.groups.insert(key, arrangements.clone());
+    arrangements
+}
+
+/// State for a depth-first search over the arrangements of mines in a group.
+struct Enumerator {
+    /// The indices of the constraints that each tile is part of.
+    tile_constraints: Vec<Vec<usize>>,
+    /// The number of mines that each constraint still needs.
+    needed: Vec<isize>,
+    /// The number of tiles in each constraint that have not been assigned yet.
+    unassigned: Vec<isize>,
+    /// Whether each tile is currently assigned to be a mine.
+    assignment: Vec<bool>,
+    mine_count: usize,
+    steps: usize,
+    arrangements: Arrangements,
+}
+
+impl Enumerator {
+    /// Try both possibilities for a tile and recurse on the tiles after it.
+    /// Return false if the search took too long.
+    fn assign(&mut self, i: usize) -> bool {
+        if i == self.assignment.len() {
+            self.arrangements.totals[self.mine_count] += 1;
+            for (j, &is_mine) in self.assignment.iter().enumerate() {
+                if is_mine {
+                    self.arrangements.mines[self.mine_count][j] += 1;
+                }
+            }
+            return true;
+        }
+        self.steps += 1;
+        if self.steps > MAX_ENUMERATION_STEPS {
+            return false;
+        }
+        for &is_mine in &[false, true] {
+            let mine = is_mine as isize;
+            let mut consistent = true;
+            for &c in &self.tile_constraints[i] {
+                self.unassigned[c] -= 1;
+                self.needed[c] -= mine;
+                if self.needed[c] < 0 || self.unassigned[c] < self.needed[c] {
+                    consistent = false;
+                }
+            }
+            self.assignment[i] = is_mine;
+            self.mine_count += is_mine as usize;
+            let finished = !consistent || self.assign(i + 1);
+            self.assignment[i] = false;
+            self.mine_count -= is_mine as usize;
+            for &c in &self.tile_constraints[i] {
+                self.unassigned[c] += 1;
+                self.needed[c] += mine;
+            }
+            if !finished {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Frontier {
+    /// Return, for each group, whether each possible number of mines in that
+    /// group is consistent with the rest of the board (including the total
+    /// number of mines).
+    fn feasible_mine_counts(&self) -> Vec<Vec<bool>> {
+        let possible: Vec<Vec<bool>> = self
+            .groups
+            .iter()
+            .map(|group| match &group.arrangements {
+                Some(arrangements) => arrangements.totals.iter().map(|&n| n > 0).collect(),
+                // Assume that any number of mines is possible.
+                None => vec![true; group.tiles.len() + 1],
+            })
+            .collect();
+        let mines_left = match self.mines_left {
+            Some(n) => n,
+            None => return possible,
+        };
+        let min_total = mines_left.saturating_sub(self.other_tiles.len());
+        possible
+            .iter()
+            .enumerate()
+            .map(|(i, counts)| {
+                // Find the possible totals among all the other groups.
+                let others = possible
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .fold(vec![true], |totals, (_, counts)| {
+                        add_counts(&totals, counts)
+                    });
+                counts
+                    .iter()
+                    .enumerate()
+                    .map(|(k, &possible)| {
+                        possible
+                            && others.iter().enumerate().any(|(j, &other_possible)| {
+                                other_possible && min_total <= k + j && k + j <= mines_left
+                            })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Return the possible numbers of mines among all the groups combined.
+    fn feasible_totals(&self, feasible: &[Vec<bool>]) -> Vec<bool> {
+        feasible
+            .iter()
+            .fold(vec![true], |totals, counts| add_counts(&totals, counts))
+    }
+}
+
+/// Given which totals are possible for two sets of tiles, return which totals
+/// are possible for both of them combined.
+fn add_counts(a: &[bool], b: &[bool]) -> Vec<bool> {
+    let mut result = vec![false; a.len() + b.len() - 1];
+    for (i, &a_possible) in a.iter().enumerate() {
+        for (j, &b_possible) in b.iter().enumerate() {
+            result[i + j] |= a_possible && b_possible;
+        }
+    }
+    result
+}
+
+/// Deduce tiles that are the same in every arrangement of mines that agrees
+/// with the numbers and with the total number of mines. Return true if anything
+/// new was deduced.
+fn apply_enumeration(frontier: &Frontier, deductions: &mut Deductions) -> bool {
+    let mut progress = false;
+    let feasible = frontier.feasible_mine_counts();
+    for (group, feasible_counts) in frontier.groups.iter().zip(&feasible) {
+        let arrangements = match &group.arrangements {
+            Some(arrangements) => arrangements,
+            None => continue,
+        };
+        let counts: Vec<usize> = (0..feasible_counts.len())
+            .filter(|&k| feasible_counts[k])
+            .collect();
+        if counts.is_empty() {
+            continue;
+        }
+        for (i, &pos) in group.tiles.iter().enumerate() {
+            if counts.iter().all(|&k| arrangements.mines[k][i] == 0) {
+                progress |= deductions.safe.insert(pos);
+            } else if counts
+                .iter()
+                .all(|&k| arrangements.mines[k][i] == arrangements.totals[k])
+            {
+                progress |= deductions.mines.insert(pos);
+            }
+        }
+    }
+    // Use the total number of mines to deduce the tiles away from the
+    // frontier.
+    if let (Some(mines_left), false) = (frontier.mines_left, frontier.other_tiles.is_empty()) {
+        let others = frontier.other_tiles.len();
+        let totals: Vec<usize> = frontier
+            .feasible_totals(&feasible)
+            .iter()
+            .enumerate()
+            .filter(|&(total, &possible)| {
+                possible && total <= mines_left && mines_left - total <= others
+            })
+            .map(|(total, _)| total)
+            .collect();
+        if !totals.is_empty() {
+            let target = if totals.iter().all(|&total| total == mines_left) {
+                Some(&mut deductions.safe)
+            } else if totals.iter().all(|&total| mines_left - total == others) {
+                Some(&mut deductions.mines)
+            } else {
+                None
+            };
+            if let Some(target) = target {
+                for &pos in &frontier.other_tiles {
+                    progress |= target.insert(pos);
+                }
+            }
+        }
+    }
+    progress
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::make_opened_board;
+
+    #[test]
+    fn solve_finds_the_last_mine() {
+        let board = make_opened_board((3, 3), &[(0, 0)], (2, 2));
+        let deductions = solve(&board);
+        assert!(deductions.safe.is_empty());
+        assert_eq!(deductions.mines, [(0, 0)].iter().copied().collect());
+    }
+
+    #[test]
+    fn solve_uses_overlapping_numbers() {
+        // The numbers along the middle row are 1 1 2 1 1, which only fit one
+        // arrangement of the top row.
+        let board = make_opened_board((3, 5), &[(0, 1), (0, 3)], (2, 2));
+        let deductions = solve(&board);
+        assert_eq!(
+            deductions.safe,
+            [(0, 0), (0, 2), (0, 4)].iter().copied().collect()
+        );
+        assert_eq!(deductions.mines, [(0, 1), (0, 3)].iter().copied().collect());
+    }
+
+    #[test]
+    fn solve_finds_nothing_in_a_fifty_fifty() {
+        let board = make_opened_board((2, 3), &[(0, 2)], (1, 0));
+        assert!(solve(&board).is_empty());
+    }
+
+    #[test]
+    fn solve_does_not_trust_flags() {
+        let mut board = make_opened_board((2, 3), &[(0, 2)], (1, 0));
+        board.right_click((1, 2), false);
+        assert!(solve(&board).is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+//! Efficiency statistics for a game, as compared by competitive players.
+//!
+//! The 3BV ("Bechtel's Board Benchmark Value") of a board is the minimum number
+//! of left clicks needed to clear it without flagging: one for each opening
+//! (a connected area of zeros, along with the numbers around it), plus one for
+//! each number that does not border an opening.
+
+use crate::board::{Board, Pos, Tile, TileState};
+use crate::game::Action;
+
+/// The number of each kind of click made during a game.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Clicks {
+    pub left: usize,
+    pub right: usize,
+    /// Left clicks on an uncovered number, which reveal its neighbors.
+    pub chord: usize,
+}
+
+impl Clicks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the clicks in a sequence of actions.
+    pub fn from_actions(actions: impl IntoIterator<Item = Action>) -> Self {
+        let mut clicks = Self::new();
+        for action in actions {
+            clicks.add(action);
+        }
+        clicks
+    }
+
+    /// Count a single action. Undo and redo are not clicks on the board, so
+    /// they are ignored.
+    pub fn add(&mut self, action: Action) {
+        match action {
+            Action::LeftClick(_) => self.left += 1,
+            Action::RightClick(_) => self.right += 1,
+            Action::Chord(_) => self.chord += 1,
+            Action::Undo | Action::Redo => (),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.left + self.right + self.chord
+    }
+}
+
+/// Return how much of the 3BV of the board has been cleared so far, along with
+/// the 3BV itself.
+pub fn get_3bv(board: &Board) -> (usize, usize) {
+    let tiles = board.get_tiles();
+    let tilestates = board.get_tilestates();
+    let mut in_opening = ndarray::Array2::from_elem(*board.get_size(), false);
+    let mut total = 0;
+    let mut solved = 0;
+
+    // Each opening counts once, and is cleared as soon as any of its zeros is.
+    for (start, &tile) in tiles.indexed_iter() {
+        if tile != Tile::Safe(0) || in_opening[start] {
+            continue;
+        }
+        total += 1;
+        let mut cleared = false;
+        let mut stack: Vec<Pos> = vec![start];
+        in_opening[start] = true;
+        while let Some(pos) = stack.pop() {
+            if tiles[pos] != Tile::Safe(0) {
+                continue;
+            }
+            cleared |= tilestates[pos] == TileState::Uncovered;
+            for neighbor_pos in board.neighbor_coords(pos) {
+                if !in_opening[neighbor_pos] {
+                    in_opening[neighbor_pos] = true;
+                    stack.push(neighbor_pos);
+                }
+            }
+        }
+        if cleared {
+            solved += 1;
+        }
+    }
+
+    // Every other number needs a click of its own.
+    for (pos, &tile) in tiles.indexed_iter() {
+        if let Tile::Safe(_) = tile {
+            if !in_opening[pos] {
+                total += 1;
+                if tilestates[pos] == TileState::Uncovered {
+                    solved += 1;
+                }
+            }
+        }
+    }
+
+    (solved, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::make_board;
+
+    #[test]
+    fn one_opening() {
+        let mut board = make_board((3, 3), &[(0, 0)]);
+        assert_eq!(get_3bv(&board), (0, 1));
+        board.left_click((2, 2));
+        assert_eq!(get_3bv(&board), (1, 1));
+    }
+
+    #[test]
+    fn numbers_away_from_openings_count_on_their_own() {
+        let mut board = make_board((2, 3), &[(0, 2)]);
+        assert_eq!(get_3bv(&board), (0, 2));
+        board.left_click((1, 0));
+        assert_eq!(get_3bv(&board), (1, 2));
+        board.left_click((1, 2));
+        assert_eq!(get_3bv(&board), (2, 2));
+    }
+
+    #[test]
+    fn clicks_from_actions() {
+        let clicks = Clicks::from_actions(vec![
+            Action::LeftClick((0, 0)),
+            Action::RightClick((0, 1)),
+            Action::Undo,
+            Action::Chord((0, 0)),
+        ]);
+        assert_eq!((clicks.left, clicks.right, clicks.chord), (1, 1, 1));
+        assert_eq!(clicks.total(), 3);
+    }
+}
@@ -0,0 +1,42 @@
+//! Boards and games with mines at known places, for unit tests.
+
+use crate::board::{Board, Pos};
+use crate::game::{FirstClick, Game, Rules};
+use crate::topology::Topology;
+
+/// Make a square board with mines at particular places. A tile that is listed
+/// more than once holds that many mines.
+pub fn make_board(size: Pos, mines: &[Pos]) -> Board {
+    let mines_per_tile = mines
+        .iter()
+        .map(|pos| mines.iter().filter(|&other| other == pos).count())
+        .max()
+        .unwrap_or(1);
+    Board::make_from_mines(
+        size,
+        Topology::Square,
+        false,
+        mines,
+        mines_per_tile as u8,
+        0,
+    )
+    .unwrap()
+}
+
+/// Make a board with mines at particular places and uncover a tile.
+pub fn make_opened_board(size: Pos, mines: &[Pos], start: Pos) -> Board {
+    let mut board = make_board(size, mines);
+    board.left_click(start);
+    board
+}
+
+/// Start a game on a board, where the first click leaves the mines where they
+/// are.
+pub fn make_game(board: Board, lives: usize) -> Game {
+    let rules = Rules {
+        first_click: FirstClick::AsIs,
+        lives,
+        ..Rules::default()
+    };
+    Game::new(board, rules)
+}
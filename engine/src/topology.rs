@@ -0,0 +1,157 @@
+//! The shape of the grid, which decides which tiles are neighbors.
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::Pos;
+
+/// The largest distance (in rows or columns) between a tile and any of its
+/// neighbors in a custom neighborhood.
+pub const MAX_OFFSET: isize = 2;
+
+/// The offsets of the neighbors of a tile on a square grid, including the tile
+/// itself.
+const SQUARE_OFFSETS: [(isize, isize); 9] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 0),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// The offsets of the neighbors of a tile in an even row of a hexagonal grid,
+/// including the tile itself. The rows above and below are shifted left
+/// relative to it, so the tiles up and down to the right are too far away.
+const HEX_EVEN_ROW_OFFSETS: [(isize, isize); 7] =
+    [(-1, -1), (-1, 0), (0, -1), (0, 0), (0, 1), (1, -1), (1, 0)];
+
+/// The offsets of the neighbors of a tile in an odd row of a hexagonal grid,
+/// including the tile itself.
+const HEX_ODD_ROW_OFFSETS: [(isize, isize); 7] =
+    [(-1, 0), (-1, 1), (0, -1), (0, 0), (0, 1), (1, 0), (1, 1)];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+    /// A square grid, where each tile has eight neighbors.
+    #[default]
+    Square,
+    /// A hexagonal grid, where each tile has six neighbors. Rows are stored
+    /// the same way as on a square grid, but every odd row (counting from 0)
+    /// is shifted half a tile to the right.
+    Hex,
+    /// A square grid where the neighbors of each tile are at a custom set of
+    /// (row, column) offsets from it. The offsets are sorted, include (0, 0),
+    /// and include the opposite of every offset, so that a tile is always a
+    /// neighbor of its neighbors. Use `Topology::custom` to make one.
+    Custom(Vec<(isize, isize)>),
+}
+
+impl Topology {
+    /// Make a custom neighborhood from a list of (row, column) offsets. The
+    /// tile itself and the opposite of each offset are added if they are
+    /// missing.
+    pub fn custom(offsets: &[(isize, isize)]) -> Result<Topology, &'static str> {
+        if offsets
+            .iter()
+            .any(|&(dy, dx)| dy.abs() > MAX_OFFSET || dx.abs() > MAX_OFFSET)
+        {
+            return Err("Neighbor offsets must be from -2 to 2");
+        }
+        let mut all_offsets = vec![(0, 0)];
+        for &(dy, dx) in offsets {
+            all_offsets.push((dy, dx));
+            all_offsets.push((-dy, -dx));
+        }
+        all_offsets.sort_unstable();
+        all_offsets.dedup();
+        if all_offsets.len() == 1 {
+            return Err("At least one neighbor offset other than 0,0 is required");
+        }
+        Ok(Topology::Custom(all_offsets))
+    }
+
+    /// Return the neighborhood of every tile up to two rows and columns away.
+    pub fn radius_2() -> Topology {
+        let offsets: Vec<(isize, isize)> = (-2..=2)
+            .flat_map(|dy| (-2..=2).map(move |dx| (dy, dx)))
+            .collect();
+        Topology::Custom(offsets)
+    }
+
+    /// Return the neighborhood of the eight tiles a knight's move away.
+    pub fn knight() -> Topology {
+        Topology::custom(&[(-2, -1), (-2, 1), (-1, -2), (-1, 2)]).unwrap()
+    }
+
+    /// Return the neighborhood of the four tiles above, below, left, and right.
+    pub fn orthogonal() -> Topology {
+        Topology::custom(&[(-1, 0), (0, -1)]).unwrap()
+    }
+
+    /// Return a short name for the topology, or `None` for a square grid.
+    pub fn get_name(&self) -> Option<&'static str> {
+        match self {
+            Topology::Square => None,
+            Topology::Hex => Some("hex"),
+            _ if *self == Topology::radius_2() => Some("radius 2"),
+            _ if *self == Topology::knight() => Some("knight"),
+            _ if *self == Topology::orthogonal() => Some("orthogonal"),
+            Topology::Custom(_) => Some("custom neighbors"),
+        }
+    }
+
+    /// Return the offsets of the neighbors of a tile in row `y`, including the
+    /// tile itself.
+    fn get_offsets(&self, y: usize) -> &[(isize, isize)] {
+        match self {
+            Topology::Square => &SQUARE_OFFSETS,
+            Topology::Hex if y % 2 == 1 => &HEX_ODD_ROW_OFFSETS,
+            Topology::Hex => &HEX_EVEN_ROW_OFFSETS,
+            Topology::Custom(offsets) => offsets,
+        }
+    }
+
+    /// Return an iterator of the coordinates of the tiles surrounding a tile
+    /// on a board of a given size, including the tile itself. If `wrap` is
+    /// true, then the left and right edges of the board touch, as do the top
+    /// and bottom edges.
+    pub fn neighbor_coords(
+        &self,
+        pos: Pos,
+        size: Pos,
+        wrap: bool,
+    ) -> impl Iterator<Item = Pos> + '_ {
+        let offsets = self.get_offsets(pos.0);
+        offsets.iter().enumerate().filter_map(move |(i, &offset)| {
+            let neighbor_pos = offset_pos(pos, offset, size, wrap)?;
+            // On a small wrapping board, two offsets can lead to the same
+            // tile, so only count it once.
+            if wrap
+                && offsets[..i]
+                    .iter()
+                    .any(|&other| offset_pos(pos, other, size, wrap) == Some(neighbor_pos))
+            {
+                return None;
+            }
+            Some(neighbor_pos)
+        })
+    }
+}
+
+/// Return the tile at an offset from another tile, or `None` if it is off the
+/// edge of a board that does not wrap.
+fn offset_pos((y, x): Pos, (dy, dx): (isize, isize), size: Pos, wrap: bool) -> Option<Pos> {
+    let (y, x) = (y as isize + dy, x as isize + dx);
+    let (height, width) = (size.0 as isize, size.1 as isize);
+    if wrap {
+        Some((y.rem_euclid(height) as usize, x.rem_euclid(width) as usize))
+    } else if 0 <= y && y < height && 0 <= x && x < width {
+        Some((y as usize, x as usize))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,113 @@
+//! Mouse button bindings, read from a config file at startup.
+
+use serde::{Deserialize, Serialize};
+use tetra::input::MouseButton;
+
+use crate::files;
+
+const BINDINGS_FILE_NAME: &str = "bindings.json";
+
+/// A mouse button that can be bound to an action.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Button {
+    Left,
+    Middle,
+    Right,
+    X1,
+    X2,
+}
+
+impl From<Button> for MouseButton {
+    fn from(button: Button) -> MouseButton {
+        match button {
+            Button::Left => MouseButton::Left,
+            Button::Middle => MouseButton::Middle,
+            Button::Right => MouseButton::Right,
+            Button::X1 => MouseButton::X1,
+            Button::X2 => MouseButton::X2,
+        }
+    }
+}
+
+/// Which mouse buttons do what.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    /// Uncover a tile, or uncover the neighbors of an uncovered number.
+    pub reveal: Button,
+    /// Cycle the flag and question mark on a tile.
+    pub flag: Button,
+    /// Uncover the neighbors of an uncovered number, if bound.
+    pub chord: Option<Button>,
+    /// Whether pressing the reveal and flag buttons together and then releasing
+    /// either one uncovers the neighbors of an uncovered number.
+    pub reveal_flag_chord: bool,
+    /// Whether the flag button uncovers the neighbors of an uncovered number.
+    pub chord_on_flag_click: bool,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            reveal: Button::Left,
+            flag: Button::Right,
+            chord: Some(Button::Middle),
+            reveal_flag_chord: true,
+            chord_on_flag_click: false,
+        }
+    }
+}
+
+impl Bindings {
+    /// Return an error if two actions are bound to the same button.
+    fn validate(&self) -> Result<(), &'static str> {
+        if self.reveal == self.flag
+            || self.chord == Some(self.reveal)
+            || self.chord == Some(self.flag)
+        {
+            Err("two actions are bound to the same button")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Load the mouse bindings, or write the default bindings to the config file
+/// (so that they can be edited) if there is none yet.
+pub fn load() -> Bindings {
+    read().unwrap_or_else(|| {
+        let bindings = Bindings::default();
+        if let Err(err) = files::write_json(BINDINGS_FILE_NAME, &bindings) {
+            println!("Could not save mouse bindings: {}", err);
+        }
+        bindings
+    })
+}
+
+/// Load the mouse bindings, or use the default bindings if there is no config
+/// file, without creating one. The terminal interface does not write to the
+/// data directory.
+pub fn load_without_writing() -> Bindings {
+    read().unwrap_or_default()
+}
+
+/// Read the mouse bindings from the config file, or return `None` if there is
+/// none. The default bindings are used if the file cannot be read or is not
+/// valid.
+fn read() -> Option<Bindings> {
+    match files::read_json::<Bindings>(BINDINGS_FILE_NAME) {
+        Ok(Some(bindings)) => match bindings.validate() {
+            Ok(()) => Some(bindings),
+            Err(msg) => {
+                println!("Could not load mouse bindings: {}", msg);
+                Some(Bindings::default())
+            }
+        },
+        Ok(None) => None,
+        Err(err) => {
+            println!("Could not load mouse bindings: {}", err);
+            Some(Bindings::default())
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! A line-based protocol for playing the game from another program, such as a
+//! solver, without a display.
+//!
+//! Each line of standard input is one command, and each command other than
+//! `quit` writes a response to standard output. See the README for the list of
+//! commands and the format of the board.
+
+use minesweeper_engine::{board, Action, Board, Difficulty, Game, GameStage, Pos, Tile, TileState};
+use std::io::{self, BufRead, Write};
+
+use crate::cli::Params;
+
+struct BotState {
+    game: Game,
+    difficulty: Difficulty,
+    /// The seed to use for every board, if one was given on the command line.
+    seed: Option<u64>,
+}
+
+/// Read commands from standard input until it ends or `quit` is read.
+pub fn run(params: Params) -> io::Result<()> {
+    let rules = params.get_rules();
+    let mut state = BotState {
+        game: Game::new(Board::make_empty((1, 1)), rules),
+        difficulty: params.difficulty,
+        seed: params.seed,
+    };
+    state
+        .new_game(None)
+        .map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?;
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        if words == ["quit"] {
+            break;
+        }
+        match state.handle_command(&words) {
+            Ok(()) => state.write_state(&mut stdout)?,
+            Err(msg) => writeln!(stdout, "error {}", msg)?,
+        }
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+impl BotState {
+    /// Start a new game with the same difficulty, from a seed if one is given.
+    fn new_game(&mut self, seed: Option<u64>) -> Result<(), &'static str> {
+        let seed = seed.or(self.seed).unwrap_or_else(board::random_seed);
+        let board = self.difficulty.new_game(seed)?;
+        self.game = Game::new(board, self.game.get_rules());
+        Ok(())
+    }
+
+    fn handle_command(&mut self, words: &[&str]) -> Result<(), &'static str> {
+        let action = match words {
+            ["state"] => return Ok(()),
+            ["new"] => return self.new_game(None),
+            ["new", seed] => {
+                let seed = seed
+                    .parse()
+                    .map_err(|_| "Seed must be a non-negative integer")?;
+                return self.new_game(Some(seed));
+            }
+            ["reveal", y, x] => Action::LeftClick(self.parse_pos(y, x)?),
+            ["flag", y, x] => Action::RightClick(self.parse_pos(y, x)?),
+            ["chord", y, x] => Action::Chord(self.parse_pos(y, x)?),
+            ["undo"] => Action::Undo,
+            ["redo"] => Action::Redo,
+            _ => return Err("Unknown command"),
+        };
+        if let Action::Chord(pos) = action {
+            if !self.game.is_over()
+                && self.game.get_board().get_tilestates()[pos] != TileState::Uncovered
+            {
+                return Err("Only uncovered tiles can be chorded");
+            }
+        }
+        match self.game.apply(action) {
+            Some(_) => Ok(()),
+            None => Err(match action {
+                Action::Undo => "Nothing to undo",
+                Action::Redo => "Nothing to redo",
+                _ => "The game is over",
+            }),
+        }
+    }
+
+    fn parse_pos(&self, y: &str, x: &str) -> Result<Pos, &'static str> {
+        let &(height, width) = self.game.get_board().get_size();
+        match (y.parse::<usize>(), x.parse::<usize>()) {
+            (Ok(y), Ok(x)) if y < height && x < width => Ok((y, x)),
+            _ => Err("Tile is not on the board"),
+        }
+    }
+
+    /// Write the stage, the number of mines left to flag, and the tiles that
+    /// the player can see.
+    fn write_state(&self, out: &mut impl Write) -> io::Result<()> {
+        let board = self.game.get_board();
+        let stage = self.game.get_stage();
+        let &(height, width) = board.get_size();
+        let stage_name = match stage {
+            GameStage::Pre => "pre",
+            GameStage::Playing => "playing",
+            GameStage::Exploded => "exploded",
+            GameStage::Complete => "complete",
+        };
+        writeln!(out, "stage {}", stage_name)?;
+        writeln!(out, "size {} {}", height, width)?;
+        writeln!(out, "mines_left {}", board.get_flags_left())?;
+        if self.game.get_rules().lives > 1 {
+            writeln!(out, "lives_left {}", self.game.get_lives_left())?;
+        }
+        for y in 0..height {
+            let row: Vec<String> = (0..width)
+                .map(|x| {
+                    tile_token(
+                        board.get_tiles()[(y, x)],
+                        board.get_tilestates()[(y, x)],
+                        stage,
+                    )
+                })
+                .collect();
+            writeln!(out, "{}", row.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Return the token that represents a tile on the board. Tokens are separated
+/// by spaces, since numbers and counts can have more than one digit.
+fn tile_token(tile: Tile, tilestate: TileState, stage: GameStage) -> String {
+    let exploded = stage == GameStage::Exploded;
+    match tilestate {
+        TileState::Hidden if exploded && tile.is_mine() => count_token('*', tile.get_mines()),
+        TileState::Hidden => ".".to_string(),
+        TileState::Flagged(n) if exploded && tile.get_mines() != n => "X".to_string(),
+        TileState::Flagged(n) => count_token('F', n),
+        TileState::QuestionMark => "?".to_string(),
+        TileState::Uncovered => match tile {
+            Tile::Mine(n) => count_token('*', n),
+            Tile::Safe(n) => n.to_string(),
+        },
+    }
+}
+
+/// Return a symbol followed by a count, which is left out if it is 1.
+fn count_token(symbol: char, count: u8) -> String {
+    if count == 1 {
+        symbol.to_string()
+    } else {
+        format!("{}{}", symbol, count)
+    }
+}
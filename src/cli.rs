@@ -1,6 +1,72 @@
 use getopts::Options;
+use minesweeper_engine::{
+    board::MAX_NO_GUESS_DENSITY, infinite::CHUNK_SIZE, Difficulty, FirstClick, Rules, Topology,
+};
+use serde::Deserialize;
+use std::path::Path;
 
-use crate::board::Difficulty;
+use crate::config::Config;
+use crate::files;
+use crate::replay::{self, Replay};
+
+/// The largest number of mines that a tile can hold, which is the largest
+/// number of flags that there are sprites for.
+pub const MAX_MINES_PER_TILE: u8 = 5;
+
+/// The largest number of lives, which keeps the count to two digits.
+pub const MAX_LIVES: usize = 99;
+
+/// Everything needed to start the game, as specified on the command line.
+pub struct Params {
+    pub difficulty: Difficulty,
+    pub scale_factor: usize,
+    pub seed: Option<u64>,
+    pub no_guess: bool,
+    pub question_marks: bool,
+    /// The number of mines that can be uncovered before the game is lost.
+    pub lives: usize,
+    /// A recorded game to play back instead of playing a new one.
+    pub replay: Option<Replay>,
+    /// Whether to play on a board with no edges. The difficulty is then the
+    /// size of one chunk of the board and the number of mines in it.
+    pub infinite: bool,
+    /// Whether to play in the terminal instead of opening a window.
+    pub tui: bool,
+    /// Whether to read commands from standard input instead of opening a
+    /// window, for playing from another program.
+    pub bot: bool,
+}
+
+impl Params {
+    /// Return the rules for every new game.
+    pub fn get_rules(&self) -> Rules {
+        Rules {
+            first_click: if self.no_guess {
+                FirstClick::NoGuess
+            } else {
+                FirstClick::Safe
+            },
+            question_marks: self.question_marks,
+            lives: self.lives,
+        }
+    }
+
+    /// Apply the options from the config file that the command line did not
+    /// override. A replay is played back with the rules it was recorded with.
+    pub fn apply_config(&mut self, config: &Config) {
+        if self.replay.is_none() && !config.question_marks {
+            self.question_marks = false;
+        }
+    }
+}
+
+/// A variant of the game read from a file, which sets which tiles count as
+/// neighbors.
+#[derive(Deserialize)]
+struct Variant {
+    /// The (row, column) offsets of the neighbors of each tile.
+    neighbors: Vec<(isize, isize)>,
+}
 
 pub fn get_opts() -> Options {
     let mut opts = Options::new();
@@ -40,10 +106,70 @@ pub fn get_opts() -> Options {
         "play with a custom mine density",
         "MINE_DENSITY",
     );
+    opts.optopt(
+        "r",
+        "seed",
+        "generate every board from the same random seed",
+        "SEED",
+    );
+    opts.optflag(
+        "",
+        "hex",
+        "play on a hexagonal grid, where each tile has six neighbors",
+    );
+    opts.optopt(
+        "",
+        "neighbors",
+        "choose which tiles count as neighbors (see below)",
+        "MASK",
+    );
+    opts.optflag(
+        "",
+        "wrap",
+        "wrap the edges of the board around, so that there are no edges",
+    );
+    opts.optflag(
+        "",
+        "infinite",
+        "play on a board with no edges, scored by the area cleared",
+    );
+    opts.optopt(
+        "",
+        "mines-per-tile",
+        "let each tile hold up to this many mines (1 to 5)",
+        "N",
+    );
+    opts.optflag(
+        "n",
+        "no-guess",
+        "only generate boards that can be solved without guessing",
+    );
+    opts.optopt(
+        "",
+        "lives",
+        "let this many mines be uncovered before the game is lost",
+        "LIVES",
+    );
+    opts.optflag(
+        "q",
+        "no-question-marks",
+        "right click only toggles flags, without question marks",
+    );
+    opts.optopt("", "replay", "play back a recorded game", "FILE");
+    opts.optflag(
+        "",
+        "tui",
+        "play in the terminal instead of opening a window",
+    );
+    opts.optflag(
+        "",
+        "bot",
+        "read commands from standard input instead of opening a window",
+    );
     opts
 }
 
-pub fn get_params_from_cli() -> Result<(Difficulty, usize), Option<&'static str>> {
+pub fn get_params_from_cli() -> Result<Params, Option<&'static str>> {
     let env_args: Vec<String> = std::env::args().collect();
     if let Ok(matches) = get_opts().parse(&env_args[1..]) {
         if matches.opt_present("h") {
@@ -66,6 +192,130 @@ pub fn get_params_from_cli() -> Result<(Difficulty, usize), Option<&'static str>
             ));
         }
         let scale_factor = scale_factor.unwrap_or(2);
+        if matches.opt_present("tui") && matches.opt_present("bot") {
+            return Err(Some("--tui and --bot are mutually exclusive"));
+        }
+        // Replay
+        if let Some(path) = matches.opt_str("replay") {
+            if matches.opt_present("tui") || matches.opt_present("bot") {
+                return Err(Some("Replays can only be played back in a window"));
+            }
+            return match replay::load(Path::new(&path)) {
+                Ok(replay) => Ok(Params {
+                    difficulty: replay.difficulty.clone(),
+                    scale_factor,
+                    seed: Some(replay.seed),
+                    no_guess: false,
+                    question_marks: replay.question_marks,
+                    lives: replay.lives,
+                    replay: Some(replay),
+                    infinite: false,
+                    tui: false,
+                    bot: false,
+                }),
+                Err(msg) => {
+                    println!("{}", msg);
+                    Err(Some("Could not load replay"))
+                }
+            };
+        }
+        // Topology
+        let topology = match matches.opt_str("neighbors") {
+            Some(_) if matches.opt_present("hex") => {
+                return Err(Some("--hex and --neighbors are mutually exclusive"))
+            }
+            Some(mask) => parse_neighbors(&mask)?,
+            None if matches.opt_present("hex") => Topology::Hex,
+            None => Topology::Square,
+        };
+        // Mines per tile
+        let mut mines_per_tile = 1;
+        if let Some(mines_per_tile_str) = matches.opt_str("mines-per-tile") {
+            match mines_per_tile_str.parse() {
+                Ok(n) if (1..=MAX_MINES_PER_TILE).contains(&n) => mines_per_tile = n,
+                _ => {
+                    return Err(Some(
+                        "Mines per tile must be an integer from 1 to 5 (inclusive)",
+                    ))
+                }
+            }
+        }
+        if 1 < mines_per_tile && matches.opt_present("n") {
+            return Err(Some(
+                "--no-guess is not available when a tile can hold more than one mine",
+            ));
+        }
+        // Lives
+        let mut lives = 1;
+        if let Some(lives_str) = matches.opt_str("lives") {
+            match lives_str.parse() {
+                Ok(n) if (1..=MAX_LIVES).contains(&n) => lives = n,
+                _ => return Err(Some("Lives must be an integer from 1 to 99 (inclusive)")),
+            }
+        }
+        // Seed
+        let mut seed: Option<u64> = None;
+        if let Some(seed_str) = matches.opt_str("r") {
+            match seed_str.parse() {
+                Ok(n) => seed = Some(n),
+                Err(_) => return Err(Some("Seed must be a non-negative integer")),
+            }
+        }
+        // Infinite board
+        if matches.opt_present("infinite") {
+            if matches.opt_present("tui") || matches.opt_present("bot") {
+                return Err(Some("Infinite boards can only be played in a window"));
+            }
+            let fixed_board = [
+                "1",
+                "2",
+                "3",
+                "x",
+                "y",
+                "m",
+                "hex",
+                "neighbors",
+                "wrap",
+                "mines-per-tile",
+                "n",
+            ];
+            if fixed_board.iter().any(|&name| matches.opt_present(name)) {
+                return Err(Some(
+                    "--infinite only takes a mine density (use -d), not a board size, mine count, topology, or other variant",
+                ));
+            }
+            let density = match matches.opt_str("d") {
+                Some(density_str) => match density_str.parse() {
+                    Ok(n) => n,
+                    Err(_) => {
+                        return Err(Some(
+                            "Mine density must be a decimal number from 0.125 to 0.5",
+                        ))
+                    }
+                },
+                // The same density as intermediate.
+                None => {
+                    let Difficulty {
+                        size: (height, width),
+                        mines,
+                        ..
+                    } = Difficulty::intermediate();
+                    mines as f32 / (height * width) as f32
+                }
+            };
+            return Ok(Params {
+                difficulty: Difficulty::with_density((CHUNK_SIZE, CHUNK_SIZE), density),
+                scale_factor,
+                seed,
+                no_guess: false,
+                question_marks: !matches.opt_present("q"),
+                lives,
+                replay: None,
+                infinite: true,
+                tui: false,
+                bot: false,
+            });
+        }
         // Preset
         let mut result: Option<Difficulty> = None;
         {
@@ -130,6 +380,7 @@ pub fn get_params_from_cli() -> Result<(Difficulty, usize), Option<&'static str>
             Some(Difficulty {
                 size: (preset_height, preset_width),
                 mines: preset_mines,
+                ..
             }) => {
                 if height == None {
                     height = Some(preset_height);
@@ -159,13 +410,31 @@ pub fn get_params_from_cli() -> Result<(Difficulty, usize), Option<&'static str>
                 mines = Some(((width * height) as f32 * density).round() as usize);
             }
             if let Some(mines) = mines {
-                return Ok((
-                    Difficulty {
+                if matches.opt_present("n")
+                    && (width * height) as f32 * MAX_NO_GUESS_DENSITY < mines as f32
+                {
+                    return Err(Some(
+                        "--no-guess is only available up to a mine density of 0.25",
+                    ));
+                }
+                return Ok(Params {
+                    difficulty: Difficulty {
                         size: (height, width),
                         mines,
+                        topology,
+                        wrap: matches.opt_present("wrap"),
+                        mines_per_tile,
                     },
                     scale_factor,
-                ));
+                    seed,
+                    no_guess: matches.opt_present("n"),
+                    question_marks: !matches.opt_present("q"),
+                    lives,
+                    replay: None,
+                    infinite: false,
+                    tui: matches.opt_present("tui"),
+                    bot: matches.opt_present("bot"),
+                });
             } else {
                 return Err(Some(
                     "A number or density of mines is required (use -m or -d)",
@@ -178,15 +447,43 @@ pub fn get_params_from_cli() -> Result<(Difficulty, usize), Option<&'static str>
     Err(None)
 }
 
-fn err_too_many_difficulties() -> Option<Difficulty> {
-    println!("Only one difficulty may be specified");
-    println!();
-    None
-}
-
-fn err_need_positive_integer(argument: &str) -> Option<Difficulty> {
-    println!("{} must be a positive integer", argument);
-    None
+/// Parse the argument of --neighbors, which is the name of a preset, a list of
+/// offsets like `-2,1;-1,2`, or the path of a JSON variant file.
+fn parse_neighbors(mask: &str) -> Result<Topology, Option<&'static str>> {
+    match mask {
+        "square" => return Ok(Topology::Square),
+        "radius2" => return Ok(Topology::radius_2()),
+        "knight" => return Ok(Topology::knight()),
+        "orthogonal" => return Ok(Topology::orthogonal()),
+        _ => (),
+    }
+    let offsets = if mask.ends_with(".json") {
+        let path = Path::new(mask);
+        match files::read_json_path::<Variant>(path) {
+            Ok(Some(variant)) => variant.neighbors,
+            Ok(None) => {
+                println!("{} does not exist", path.display());
+                return Err(Some("Could not load variant"));
+            }
+            Err(err) => {
+                println!("Could not read {}: {}", path.display(), err);
+                return Err(Some("Could not load variant"));
+            }
+        }
+    } else {
+        let mut offsets = vec![];
+        for offset in mask.split(';') {
+            let mut parts = offset.split(',').map(|part| part.trim().parse::<isize>());
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(Ok(dy)), Some(Ok(dx)), None) => offsets.push((dy, dx)),
+                _ => return Err(Some(
+                    "Neighbors must be a preset, a list of offsets like -2,1;-1,2, or a .json file",
+                )),
+            }
+        }
+        offsets
+    };
+    Topology::custom(&offsets).map_err(Some)
 }
 
 pub fn print_usage() {
@@ -203,4 +500,64 @@ pub fn print_usage() {
     println!();
     println!("If width is specified but not height (or vice versa), the board");
     println!("is assumed to be square.");
+    println!();
+    println!("The seed of each board is printed when the board is created. The");
+    println!("same seed, board size, and mine count always produce the same");
+    println!("board.");
+    println!();
+    println!("With --hex, tiles are drawn as hexagons, odd rows are shifted");
+    println!("half a tile to the right, and each tile touches the two tiles");
+    println!("beside it and two tiles in each of the rows above and below.");
+    println!("Best times and statistics are kept separately from square");
+    println!("boards.");
+    println!();
+    println!("--neighbors chooses which tiles count as neighbors on a square");
+    println!("grid. MASK is one of the presets \"square\" (the default),");
+    println!("\"radius2\", \"knight\", or \"orthogonal\"; a list of row,column");
+    println!("offsets such as \"-2,1;-1,2\" (the opposite of each offset is");
+    println!("added automatically); or a .json file with a \"neighbors\" list of");
+    println!("[row, column] offsets. Offsets must be from -2 to 2.");
+    println!();
+    println!("With --wrap, tiles on the left edge touch tiles on the right");
+    println!("edge, and tiles on the top edge touch tiles on the bottom edge.");
+    println!("Wrapping hexagonal boards must have an even height.");
+    println!();
+    println!("With --mines-per-tile, a tile can hold more than one mine, and");
+    println!("each number counts every mine on the tiles around it. Right click");
+    println!("adds flags up to the same limit before removing them. Hints,");
+    println!("probabilities, auto-flag, and --no-guess are not available.");
+    println!();
+    println!("With --lives, uncovering a mine costs a life instead of ending");
+    println!("the game, until the last life is lost. Each mine costs a life,");
+    println!("so a tile with more than one mine costs more than one. The mine");
+    println!("stays uncovered and counts as flagged. Games where a life was");
+    println!("lost are marked as assisted.");
+    println!();
+    println!("With --infinite, the board has no edges, and mines are placed");
+    println!("as tiles near them are uncovered. The arrow keys (or WASD or");
+    println!("HJKL) pan the view, by a whole chunk while holding shift, and");
+    println!("Home pans back to the start. The score is the number of safe");
+    println!("tiles cleared. Only -d, --lives, --seed, and -q can be used with");
+    println!("it, and replays, saved games, and statistics are not kept.");
+    println!();
+    println!("With --no-guess, mines are rearranged after the first click until");
+    println!("the board can be solved from there by pure logic. The mine");
+    println!("density can be at most 0.25. If no such board is found after");
+    println!("1,000 tries, the game starts on a board that may need guessing.");
+    println!();
+    println!("Question marks can also be turned off by setting");
+    println!("\"question_marks\" to false in data/config.json.");
+    println!();
+    println!("With --tui, the game is drawn in the terminal, which must");
+    println!("support colors and mouse reporting. Replays, saved games, and");
+    println!("statistics are only kept when playing in a window.");
+    println!();
+    println!("With --bot, commands such as \"reveal 3 4\" are read from");
+    println!("standard input, one per line, and the board is written to");
+    println!("standard output after each one. See the README for details.");
+    println!();
+    println!("When playing back a replay, the board size and mine count come");
+    println!("from the replay. Space pauses, Up and Down change the speed,");
+    println!("Left and Right seek by 5 seconds, comma and period step through");
+    println!("inputs one at a time, and Home and End jump to either end.");
 }
@@ -0,0 +1,48 @@
+//! Game options, read from a config file at startup.
+
+use serde::{Deserialize, Serialize};
+
+use crate::files;
+
+const CONFIG_FILE_NAME: &str = "config.json";
+
+/// Options that can be set in the config file. Some of them can be overridden
+/// on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Whether right clicking a flag turns it into a question mark, rather
+    /// than removing it.
+    pub question_marks: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            question_marks: true,
+        }
+    }
+}
+
+/// Load the options, or return the default options if there is no config file.
+pub fn load() -> Config {
+    match files::read_json(CONFIG_FILE_NAME) {
+        Ok(Some(config)) => config,
+        Ok(None) => Config::default(),
+        Err(err) => {
+            println!("Could not load options: {}", err);
+            Config::default()
+        }
+    }
+}
+
+/// Write the default options to the config file if there is none yet, so that
+/// they can be edited.
+pub fn create_if_missing() {
+    if files::data_path(CONFIG_FILE_NAME).exists() {
+        return;
+    }
+    if let Err(err) = files::write_json(CONFIG_FILE_NAME, &Config::default()) {
+        println!("Could not save options: {}", err);
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The directory where saved games and other persistent data are kept.
+const DATA_DIR: &str = "./data";
+
+/// Return the path of a file in the data directory.
+pub fn data_path(file_name: &str) -> PathBuf {
+    Path::new(DATA_DIR).join(file_name)
+}
+
+/// Read a value from a JSON file in the data directory. Return `Ok(None)` if
+/// the file does not exist.
+pub fn read_json<T: DeserializeOwned>(file_name: &str) -> io::Result<Option<T>> {
+    read_json_path(&data_path(file_name))
+}
+
+/// Read a value from a JSON file anywhere. Return `Ok(None)` if the file does
+/// not exist.
+pub fn read_json_path<T: DeserializeOwned>(path: &Path) -> io::Result<Option<T>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Write a value to a JSON file in the data directory, creating any missing
+/// directories.
+pub fn write_json<T: Serialize>(file_name: &str, value: &T) -> io::Result<()> {
+    let path = data_path(file_name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string(value)?)
+}
+
+/// Delete a file from the data directory, if it exists.
+pub fn remove(file_name: &str) -> io::Result<()> {
+    match fs::remove_file(data_path(file_name)) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,249 @@
+//! A window for playing on an infinite board. The window shows a fixed number
+//! of tiles, and the view pans across the board with the keyboard.
+
+use minesweeper_engine::{
+    board, infinite::CHUNK_SIZE, GameStage, InfiniteGame, InfinitePos, Rules, TileState, Topology,
+};
+use tetra::{
+    input::{self, Key},
+    Context, ContextBuilder, State,
+};
+
+use crate::bindings::{self, Bindings};
+use crate::cli::{self, Params};
+use crate::render::RenderState;
+use crate::ui::{self, ClickableBoard, TileClicks};
+use crate::GameState;
+
+/// The number of tiles shown at once (height, width).
+pub const VIEW_SIZE: (usize, usize) = (16, 30);
+
+/// The number of ticks that a pan key must be held down before the view keeps
+/// panning on its own.
+const PAN_DELAY_TICKS: usize = 20;
+
+/// The number of ticks between each step of the view while a pan key is held
+/// down.
+const PAN_REPEAT_TICKS: usize = 3;
+
+pub struct InfiniteState {
+    pub game: InfiniteGame,
+    /// The number of mines in each chunk of every new game.
+    mines_per_chunk: usize,
+    /// The seed to use for every board, if one was given on the command line.
+    seed: Option<u64>,
+    /// The rules for every new game.
+    rules: Rules,
+    pub render_state: RenderState,
+    bindings: Bindings,
+    /// The tile in the top left corner of the view.
+    pub camera: InfinitePos,
+    /// The number of ticks until the view pans again while a pan key is held
+    /// down.
+    pan_ticks: usize,
+    pub tile_clicks: TileClicks<InfinitePos>,
+    pub face_clicked: bool,
+    pub seconds: usize,
+    ticks: usize,
+}
+
+/// Open a window and play on an infinite board until it is closed.
+pub fn run(params: Params) -> tetra::Result {
+    // The view is wide enough for the number of lives without widening it.
+    let window_size =
+        GameState::get_window_size(&VIEW_SIZE, &Topology::Square, 1, params.scale_factor);
+    let mut ctx = ContextBuilder::new("Minesweeper", window_size.0, window_size.1)
+        .show_mouse(true)
+        .build()?;
+    let mut infinite_state = InfiniteState::new(&mut ctx, params)?;
+    ctx.run(&mut infinite_state)?;
+    if infinite_state.game.get_stage() == GameStage::Playing {
+        infinite_state.print_score();
+    }
+    Ok(())
+}
+
+impl InfiniteState {
+    pub fn new(ctx: &mut Context, params: Params) -> tetra::Result<Self> {
+        let rules = params.get_rules();
+        let mines_per_chunk = params.difficulty.mines;
+        let seed = params.seed.unwrap_or_else(board::random_seed);
+        let game = match InfiniteGame::new(mines_per_chunk, seed, rules) {
+            Ok(game) => game,
+            Err(msg) => {
+                println!();
+                println!("Could not start game: {}", msg);
+                println!();
+                cli::print_usage();
+                std::process::exit(1);
+            }
+        };
+        println!("Board seed: {}", seed);
+        Ok(Self {
+            game,
+            mines_per_chunk,
+            seed: params.seed,
+            rules,
+            render_state: RenderState::new(ctx)?,
+            bindings: bindings::load(),
+            camera: home_camera(),
+            pan_ticks: 0,
+            tile_clicks: TileClicks::new(),
+            face_clicked: false,
+            seconds: 0,
+            ticks: tetra::time::get_tick_rate(ctx).round() as usize,
+        })
+    }
+
+    /// Start a new game using the seed from the command line, or a random seed
+    /// if none was given.
+    fn reset_game(&mut self, ctx: &mut Context) {
+        if self.game.get_stage() == GameStage::Playing {
+            self.print_score();
+        }
+        let seed = self.seed.unwrap_or_else(board::random_seed);
+        // The number of mines was checked when the first game started.
+        self.game = InfiniteGame::new(self.mines_per_chunk, seed, self.rules).unwrap();
+        println!("Board seed: {}", seed);
+        self.camera = home_camera();
+        self.seconds = 0;
+        self.ticks = tetra::time::get_tick_rate(ctx).round() as usize;
+    }
+
+    fn print_score(&self) {
+        println!(
+            "Cleared {} tiles in {} seconds",
+            self.game.get_cleared(),
+            self.seconds
+        );
+    }
+
+    /// Move the view with the arrow keys, WASD, or HJKL, by a whole chunk at a
+    /// time while holding shift. Holding a key down keeps the view moving.
+    /// Home moves the view back to where it started. R or F2 starts a new
+    /// game.
+    fn handle_keys(&mut self, ctx: &mut Context) {
+        if input::is_key_pressed(ctx, Key::R) || input::is_key_pressed(ctx, Key::F2) {
+            self.reset_game(ctx);
+        }
+        if input::is_key_pressed(ctx, Key::Home) {
+            self.camera = home_camera();
+        }
+
+        let down = |keys: &[Key]| keys.iter().any(|&key| input::is_key_down(ctx, key));
+        let pressed = |keys: &[Key]| keys.iter().any(|&key| input::is_key_pressed(ctx, key));
+        let directions: [(&[Key], (i64, i64)); 4] = [
+            (&[Key::Up, Key::W, Key::K], (-1, 0)),
+            (&[Key::Down, Key::S, Key::J], (1, 0)),
+            (&[Key::Left, Key::A, Key::H], (0, -1)),
+            (&[Key::Right, Key::D, Key::L], (0, 1)),
+        ];
+        if directions.iter().any(|(keys, _)| pressed(keys)) {
+            self.pan_ticks = PAN_DELAY_TICKS;
+        } else if directions.iter().any(|(keys, _)| down(keys)) && self.pan_ticks <= 1 {
+            self.pan_ticks = PAN_REPEAT_TICKS;
+        } else {
+            self.pan_ticks = self.pan_ticks.saturating_sub(1);
+            return;
+        }
+        let step = if down(&[Key::LShift, Key::RShift]) {
+            CHUNK_SIZE as i64
+        } else {
+            1
+        };
+        for (keys, (dy, dx)) in &directions {
+            if down(keys) {
+                self.camera.0 += dy * step;
+                self.camera.1 += dx * step;
+            }
+        }
+    }
+
+    /// Handle presses and releases of every mouse button bound to an action on
+    /// the board, the same way as on a normal board.
+    fn handle_tile_clicks(&mut self, ctx: &mut Context) {
+        let hover_tile = self.get_tile_at_cursor(ctx);
+        let bindings = self.bindings;
+        ui::handle_tile_clicks(self, ctx, &bindings, hover_tile);
+    }
+
+    /// Apply a click to the game, and report the score if the game ended.
+    fn apply(&mut self, click: impl FnOnce(&mut InfiniteGame) -> Vec<InfinitePos>) {
+        let lives_before = self.game.get_lives_left();
+        click(&mut self.game);
+        let lives_left = self.game.get_lives_left();
+        if self.game.is_over() {
+            self.print_score();
+        } else if lives_left < lives_before {
+            println!("Lost a life ({} left)", lives_left);
+        }
+    }
+
+    fn handle_face_click(&mut self, ctx: &mut Context) {
+        let button = input::MouseButton::Left;
+        if self.render_state.is_mouse_on_face(ctx) {
+            if input::is_mouse_button_pressed(ctx, button) {
+                self.face_clicked = true;
+            } else if input::is_mouse_button_released(ctx, button) && self.face_clicked {
+                self.reset_game(ctx);
+                self.face_clicked = false;
+            }
+        }
+    }
+}
+
+impl ClickableBoard<InfinitePos> for InfiniteState {
+    fn get_tile_clicks(&mut self) -> &mut TileClicks<InfinitePos> {
+        &mut self.tile_clicks
+    }
+
+    fn is_uncovered(&self, pos: InfinitePos) -> bool {
+        self.game.get_tilestate(pos) == TileState::Uncovered
+    }
+
+    fn reveal(&mut self, _ctx: &mut Context, pos: InfinitePos) {
+        self.apply(|game| game.left_click(pos));
+    }
+
+    fn flag(&mut self, _ctx: &mut Context, pos: InfinitePos) {
+        self.apply(|game| game.right_click(pos));
+    }
+
+    fn chord(&mut self, _ctx: &mut Context, pos: InfinitePos) {
+        self.apply(|game| game.chord(pos));
+    }
+}
+
+impl State for InfiniteState {
+    fn update(&mut self, ctx: &mut Context) -> tetra::Result {
+        if self.game.get_stage() == GameStage::Playing {
+            if self.ticks <= 1 {
+                self.seconds += 1;
+                self.ticks = tetra::time::get_tick_rate(ctx).round() as usize;
+            } else {
+                self.ticks -= 1;
+            }
+        }
+        if !self.game.is_over() {
+            self.handle_tile_clicks(ctx);
+        }
+        self.handle_keys(ctx);
+        self.handle_face_click(ctx);
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context, _dt: f64) -> tetra::Result {
+        self.draw_borders(ctx);
+        self.draw_tiles(ctx);
+        self.draw_score(ctx);
+        self.draw_lives(ctx);
+        self.draw_timer(ctx);
+        self.draw_face(ctx);
+        Ok(())
+    }
+}
+
+/// Return the camera that puts the tile at (0, 0) in the middle of the view.
+fn home_camera() -> InfinitePos {
+    (-(VIEW_SIZE.0 as i64 / 2), -(VIEW_SIZE.1 as i64 / 2))
+}
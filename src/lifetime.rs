@@ -0,0 +1,166 @@
+use minesweeper_engine::Difficulty;
+use serde::{Deserialize, Serialize};
+
+use crate::files;
+
+const LIFETIME_FILE_NAME: &str = "stats.json";
+
+/// Statistics for every game played at one difficulty.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DifficultyStats {
+    pub played: usize,
+    pub won: usize,
+    pub lost: usize,
+    /// The number of games won in a row, up to the most recent game.
+    pub current_streak: usize,
+    pub best_streak: usize,
+    /// The sum of the times of every timed win, for the average time.
+    pub total_seconds: usize,
+    /// The number of wins that count towards the best and average times.
+    /// Assisted wins do not.
+    pub timed_wins: usize,
+    pub best_seconds: Option<usize>,
+}
+
+impl DifficultyStats {
+    /// Return the fraction of games that were won, or `None` if no games have
+    /// been played.
+    pub fn get_win_rate(&self) -> Option<f64> {
+        if self.played == 0 {
+            None
+        } else {
+            Some(self.won as f64 / self.played as f64)
+        }
+    }
+
+    /// Return the average time of every timed win, or `None` if there are
+    /// none.
+    pub fn get_average_seconds(&self) -> Option<f64> {
+        if self.timed_wins == 0 {
+            None
+        } else {
+            Some(self.total_seconds as f64 / self.timed_wins as f64)
+        }
+    }
+
+    fn record_win(&mut self, seconds: usize, assisted: bool) {
+        self.played += 1;
+        self.won += 1;
+        self.current_streak += 1;
+        self.best_streak = self.best_streak.max(self.current_streak);
+        if !assisted {
+            self.total_seconds += seconds;
+            self.timed_wins += 1;
+            self.best_seconds = Some(self.best_seconds.map_or(seconds, |best| best.min(seconds)));
+        }
+    }
+
+    fn record_loss(&mut self) {
+        self.played += 1;
+        self.lost += 1;
+        self.current_streak = 0;
+    }
+}
+
+/// Statistics for one difficulty, along with the difficulty and the rules that
+/// change the odds of winning, since games with different rules are kept apart.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Entry {
+    difficulty: Difficulty,
+    /// Whether the boards were generated with `--no-guess`.
+    #[serde(default)]
+    no_guess: bool,
+    /// The number of mines that could be uncovered before losing.
+    #[serde(default = "default_lives")]
+    lives: usize,
+    stats: DifficultyStats,
+}
+
+fn default_lives() -> usize {
+    1
+}
+
+/// Statistics for every difficulty that has been played, kept across sessions.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LifetimeStats {
+    entries: Vec<Entry>,
+}
+
+impl Entry {
+    fn matches(&self, difficulty: &Difficulty, no_guess: bool, lives: usize) -> bool {
+        self.difficulty == *difficulty && self.no_guess == no_guess && self.lives == lives
+    }
+}
+
+impl LifetimeStats {
+    /// Return the statistics for a difficulty with the given rules, which are
+    /// all zero if it has never been played that way.
+    pub fn get(&self, difficulty: &Difficulty, no_guess: bool, lives: usize) -> DifficultyStats {
+        self.entries
+            .iter()
+            .find(|entry| entry.matches(difficulty, no_guess, lives))
+            .map_or_else(DifficultyStats::default, |entry| entry.stats.clone())
+    }
+
+    fn get_mut(
+        &mut self,
+        difficulty: &Difficulty,
+        no_guess: bool,
+        lives: usize,
+    ) -> &mut DifficultyStats {
+        let index = match self
+            .entries
+            .iter()
+            .position(|entry| entry.matches(difficulty, no_guess, lives))
+        {
+            Some(index) => index,
+            None => {
+                self.entries.push(Entry {
+                    difficulty: difficulty.clone(),
+                    no_guess,
+                    lives,
+                    stats: DifficultyStats::default(),
+                });
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[index].stats
+    }
+
+    /// Record a game that was won. Assisted wins count towards the number of
+    /// games won, but not towards the best or average time.
+    pub fn record_win(
+        &mut self,
+        difficulty: &Difficulty,
+        no_guess: bool,
+        lives: usize,
+        seconds: usize,
+        assisted: bool,
+    ) {
+        self.get_mut(difficulty, no_guess, lives)
+            .record_win(seconds, assisted);
+    }
+
+    /// Record a game that was lost.
+    pub fn record_loss(&mut self, difficulty: &Difficulty, no_guess: bool, lives: usize) {
+        self.get_mut(difficulty, no_guess, lives).record_loss();
+    }
+}
+
+/// Load the lifetime statistics, or return empty statistics if there are none.
+pub fn load() -> LifetimeStats {
+    match files::read_json(LIFETIME_FILE_NAME) {
+        Ok(lifetime_stats) => lifetime_stats.unwrap_or_default(),
+        Err(err) => {
+            println!("Could not load statistics: {}", err);
+            LifetimeStats::default()
+        }
+    }
+}
+
+/// Save the lifetime statistics, replacing the ones that were saved before.
+pub fn store(lifetime_stats: &LifetimeStats) {
+    if let Err(err) = files::write_json(LIFETIME_FILE_NAME, lifetime_stats) {
+        println!("Could not save statistics: {}", err);
+    }
+}
@@ -1,57 +1,85 @@
-#![allow(dead_code)]
-
-#[macro_use]
-extern crate ndarray;
-
-mod board;
+mod bindings;
+mod bot;
 mod cli;
+mod config;
+mod files;
+mod infinite;
+mod lifetime;
+mod playback;
 mod render;
+mod replay;
+mod save;
+mod scores;
 mod sprites;
+mod stats;
+#[cfg(unix)]
+mod tui;
 mod ui;
 mod utils;
 
+use minesweeper_engine::{
+    board, stats::Clicks, Board, Difficulty, FirstClick, Game, GameStage, Rules,
+};
 use tetra::{Context, ContextBuilder, State};
 
-use board::{Board, Difficulty};
-
-#[derive(Debug, PartialEq)]
-enum GameStage {
-    Pre,
-    Playing,
-    Exploded,
-    Complete,
-}
+use cli::Params;
+use save::SavedGame;
 
 struct GameState {
-    board: Board,
+    game: Game,
     difficulty: Difficulty,
     render_state: render::RenderState,
     ui_state: ui::UIState,
     scale_factor: usize,
-    stage: GameStage,
+    bindings: bindings::Bindings,
+    /// The seed to use for every board, if one was given on the command line.
+    seed: Option<u64>,
+    /// The rules for every new game.
+    rules: Rules,
     seconds: usize,
     ticks: usize,
+    recorder: replay::Recorder,
+    high_scores: scores::HighScores,
+    lifetime_stats: lifetime::LifetimeStats,
+    /// Whether this game has been counted in the lifetime statistics, and its
+    /// replay and any high score recorded. Only the first time a game ends
+    /// counts, even if it is undone and continued.
+    result_recorded: bool,
+    /// The replay being played back, if in playback mode.
+    player: Option<replay::Player>,
 }
 
 impl GameState {
-    pub fn new(
-        ctx: &mut Context,
-        difficulty: Difficulty,
-        scale_factor: usize,
-    ) -> tetra::Result<Self> {
-        match difficulty.new_game() {
+    pub fn new(ctx: &mut Context, params: Params) -> tetra::Result<Self> {
+        let rules = params.get_rules();
+        let mut game_state = GameState {
+            game: Game::new(Board::make_empty((1, 1)), Rules::default()),
+            difficulty: params.difficulty,
+            render_state: render::RenderState::new(ctx)?,
+            ui_state: ui::UIState::new(),
+            scale_factor: params.scale_factor,
+            bindings: bindings::load(),
+            seed: params.seed,
+            rules,
+            seconds: 0,
+            ticks: 0,
+            recorder: replay::Recorder::new(),
+            high_scores: scores::load(),
+            lifetime_stats: lifetime::load(),
+            result_recorded: false,
+            player: None,
+        };
+        if let Some(replay) = params.replay {
+            game_state.start_playback(ctx, replay);
+            return Ok(game_state);
+        }
+        match game_state.new_board() {
             Ok(board) => {
-                let mut game_state = GameState {
-                    board: Board::make_empty((1, 1)),
-                    difficulty: difficulty,
-                    render_state: render::RenderState::new(ctx)?,
-                    ui_state: ui::UIState::new(),
-                    scale_factor,
-                    stage: GameStage::Pre,
-                    seconds: 0,
-                    ticks: 0,
-                };
                 game_state.set_board(ctx, board);
+                if let Some(saved_game) = save::load() {
+                    game_state.ui_state.overlay =
+                        Some(ui::Overlay::ResumePrompt(Box::new(saved_game)));
+                }
                 Ok(game_state)
             }
             Err(msg) => {
@@ -67,17 +95,160 @@ impl GameState {
 
 impl GameState {
     pub fn reset_game(&mut self, ctx: &mut Context) {
-        self.set_board(
-            ctx,
-            self.difficulty.new_game().unwrap_or_else(|err| panic!(err)),
-        );
+        if self.player.is_some() {
+            self.restart_playback(ctx);
+            return;
+        }
+        self.set_board(ctx, self.new_board().unwrap_or_else(|err| panic!(err)));
     }
 
+    /// Make a new board using the seed from the command line, or a random seed
+    /// if none was given.
+    fn new_board(&self) -> Result<Board, &'static str> {
+        let seed = self.seed.unwrap_or_else(board::random_seed);
+        self.difficulty.new_game(seed)
+    }
+
+    /// Start a new game on a board. A replay already has the mines where they
+    /// ended up, so they are not moved on the first click.
     fn set_board(&mut self, ctx: &mut Context, board: Board) {
-        self.board = board;
+        let mut rules = self.rules;
+        if self.player.is_some() {
+            rules.first_click = FirstClick::AsIs;
+        }
+        self.set_game(ctx, Game::new(board, rules));
+    }
+
+    fn set_game(&mut self, ctx: &mut Context, game: Game) {
+        println!("Board seed: {}", game.get_board().get_seed());
+        self.game = game;
         self.reset_window_size(ctx);
         self.reset_timer(ctx);
-        self.stage = GameStage::Pre;
+        self.recorder = replay::Recorder::new();
+        self.result_recorded = false;
+        self.ui_state.hint = None;
+        tetra::window::set_title(ctx, "Minesweeper");
+    }
+
+    /// Replace the current game with one that was saved earlier.
+    pub fn resume(&mut self, ctx: &mut Context, saved_game: SavedGame) {
+        self.difficulty = saved_game.difficulty;
+        let clicks = Clicks::from_actions(saved_game.inputs.iter().map(|input| input.action));
+        let rules = Rules {
+            first_click: if saved_game.no_guess {
+                FirstClick::NoGuess
+            } else {
+                FirstClick::Safe
+            },
+            lives: saved_game.lives,
+            ..self.rules
+        };
+        let game = Game::resume(
+            saved_game.board,
+            saved_game.stage,
+            rules,
+            saved_game.assisted,
+            clicks,
+        );
+        self.set_game(ctx, game);
+        self.seconds = saved_game.seconds;
+        self.recorder = replay::Recorder::resume(saved_game.inputs);
+        self.show_assisted(ctx);
+    }
+
+    /// Save the current game if it is still in progress, so that it can be
+    /// resumed on the next launch.
+    pub fn save_on_exit(self) {
+        if self.game.get_stage() == GameStage::Playing && self.player.is_none() {
+            let no_guess = self.is_no_guess();
+            save::store(&SavedGame {
+                board: self.game.get_board().clone(),
+                stage: self.game.get_stage(),
+                seconds: self.seconds,
+                difficulty: self.difficulty,
+                assisted: self.game.is_assisted(),
+                inputs: self.recorder.get_inputs().to_vec(),
+                lives: self.game.get_rules().lives,
+                no_guess,
+            });
+        }
+    }
+
+    /// Report the result and statistics of a game that just ended, update the
+    /// lifetime statistics, write its replay, and ask for a name if the time is
+    /// a new high score.
+    fn end_game(&mut self) {
+        if self.player.is_some() {
+            return;
+        }
+        let stage = self.game.get_stage();
+        let assisted = self.game.is_assisted();
+        if stage == GameStage::Complete {
+            if assisted {
+                println!("Cleared the board in {} seconds (assisted)", self.seconds);
+            } else {
+                println!("Cleared the board in {} seconds", self.seconds);
+            }
+        }
+        // A game can end again after an undo, but its report is only printed
+        // and its result only recorded the first time.
+        if self.result_recorded {
+            return;
+        }
+        self.result_recorded = true;
+        stats::print_report(self.game.get_board(), &self.game.get_clicks(), self.seconds);
+        if self.game.get_hints() > 0 {
+            println!("Hints: {} (assisted)", self.game.get_hints());
+        }
+        let no_guess = self.is_no_guess();
+        let lives = self.game.get_rules().lives;
+        if stage == GameStage::Complete {
+            self.lifetime_stats.record_win(
+                &self.difficulty,
+                no_guess,
+                lives,
+                self.seconds,
+                assisted,
+            );
+        } else {
+            self.lifetime_stats
+                .record_loss(&self.difficulty, no_guess, lives);
+        }
+        lifetime::store(&self.lifetime_stats);
+        let replay = self.recorder.finish(
+            self.game.get_board(),
+            &self.difficulty,
+            stage,
+            self.seconds,
+            assisted,
+            self.game.get_rules(),
+        );
+        self.recorder.save(&replay);
+        if stage == GameStage::Complete
+            && !assisted
+            && self
+                .high_scores
+                .qualifies(&self.difficulty, no_guess, self.seconds)
+        {
+            self.ui_state.overlay = Some(ui::Overlay::NameEntry {
+                seconds: self.seconds,
+                name: self.high_scores.last_name.clone(),
+            });
+        }
+    }
+
+    /// Return whether the board was generated with `--no-guess`. These games
+    /// are kept apart from the others in the best times and statistics.
+    pub fn is_no_guess(&self) -> bool {
+        self.game.get_rules().first_click == FirstClick::NoGuess
+    }
+
+    /// Show in the title bar if this game is assisted, so that it does not
+    /// count as a clean win.
+    pub fn show_assisted(&mut self, ctx: &mut Context) {
+        if self.game.is_assisted() && self.player.is_none() {
+            tetra::window::set_title(ctx, "Minesweeper (assisted)");
+        }
     }
 
     fn reset_timer(&mut self, ctx: &mut Context) {
@@ -92,22 +263,34 @@ impl GameState {
 
 impl State for GameState {
     fn update(&mut self, ctx: &mut Context) -> tetra::Result {
-        if self.stage == GameStage::Playing {
-            if self.ticks <= 1 {
-                self.seconds += 1;
-                self.reset_ticks(ctx);
-            } else {
-                self.ticks -= 1;
+        let stage_before = self.game.get_stage();
+        if self.ui_state.overlay.is_some() {
+            // The first click is applied from an overlay while a board is
+            // being generated.
+            self.handle_overlay_keys(ctx);
+        } else if self.player.is_some() {
+            self.update_playback(ctx);
+        } else {
+            if self.game.get_stage() == GameStage::Playing {
+                if self.ticks <= 1 {
+                    self.seconds += 1;
+                    self.reset_ticks(ctx);
+                } else {
+                    self.ticks -= 1;
+                }
+            }
+            if !self.game.is_over() {
+                self.handle_tile_clicks(ctx);
             }
+            self.handle_keys(ctx);
+            self.handle_cursor_keys(ctx);
         }
-        if let GameStage::Pre | GameStage::Playing = self.stage {
-            self.handle_tile_left_click(ctx);
-            self.handle_tile_right_click(ctx);
+        if self.game.get_stage() != stage_before && self.game.is_over() {
+            self.end_game();
         }
-        if self.stage == GameStage::Playing && self.board.get_safe_squares_left() == 0 {
-            self.stage = GameStage::Complete;
+        if self.ui_state.overlay.is_none() {
+            self.handle_face_click(ctx);
         }
-        self.handle_face_click(ctx);
         Ok(())
     }
 
@@ -115,38 +298,84 @@ impl State for GameState {
         self.draw_borders(ctx);
         self.draw_tiles(ctx);
         self.draw_mine_counter(ctx);
+        self.draw_lives(ctx);
         self.draw_timer(ctx);
         self.draw_face(ctx);
+        self.draw_replay_cursor(ctx);
+        self.draw_overlay(ctx);
         Ok(())
     }
 }
 
 fn main() -> tetra::Result {
-    println!();
     match cli::get_params_from_cli() {
-        Ok((difficulty, scale_factor)) => {
+        // Standard output is only for the protocol, so print nothing else. The
+        // config file is not read either, so that the rules only depend on the
+        // command line.
+        Ok(params) if params.bot => {
+            if let Err(err) = bot::run(params) {
+                eprintln!("Could not run bot: {}", err);
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Ok(mut params) => {
+            params.apply_config(&config::load());
+            println!();
             println!(
                 "{} {} created by {}",
                 env!("CARGO_PKG_NAME"),
                 env!("CARGO_PKG_VERSION"),
                 env!("CARGO_PKG_AUTHORS")
             );
-            let window_size = GameState::get_window_size(&difficulty.size, scale_factor);
-            ContextBuilder::new("Minesweeper", window_size.0, window_size.1)
+            if params.tui {
+                run_tui(params);
+                return Ok(());
+            }
+            config::create_if_missing();
+            if params.infinite {
+                return infinite::run(params);
+            }
+            let window_size = GameState::get_window_size(
+                &params.difficulty.size,
+                &params.difficulty.topology,
+                params.lives,
+                params.scale_factor,
+            );
+            let mut ctx = ContextBuilder::new("Minesweeper", window_size.0, window_size.1)
                 .show_mouse(true)
-                .build()?
-                .run_with(|ctx| GameState::new(ctx, difficulty, scale_factor))
+                .build()?;
+            let mut game_state = GameState::new(&mut ctx, params)?;
+            ctx.run(&mut game_state)?;
+            game_state.save_on_exit();
+            Ok(())
         }
         Err(err) => match err {
             Some(s) => {
+                println!();
                 println!("Could not start game: {}", s);
                 println!("Use -h to view usage.");
                 std::process::exit(1);
             }
             None => {
+                println!();
                 cli::print_usage();
                 std::process::exit(1);
             }
         },
     }
 }
+
+#[cfg(unix)]
+fn run_tui(params: Params) {
+    if let Err(err) = tui::run(params) {
+        println!("Could not run in the terminal: {}", err);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(unix))]
+fn run_tui(_params: Params) {
+    println!("Playing in the terminal is only supported on Unix-like systems");
+    std::process::exit(1);
+}
@@ -0,0 +1,119 @@
+use tetra::{
+    input::{self, Key},
+    Context,
+};
+
+use crate::replay::{Player, Replay};
+use crate::GameState;
+
+/// The number of milliseconds to seek forward or backward at a time.
+const SEEK_MILLIS: f64 = 5000.0;
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 16.0;
+
+impl GameState {
+    /// Start playing back a replay from the beginning.
+    pub fn start_playback(&mut self, ctx: &mut Context, replay: Replay) {
+        self.player = Some(Player::new(replay));
+        self.restart_playback(ctx);
+    }
+
+    /// Reset the board to how it was before the first input of the replay.
+    pub fn restart_playback(&mut self, ctx: &mut Context) {
+        if let Some(player) = &mut self.player {
+            player.rewind();
+            let board = player.make_board();
+            self.set_board(ctx, board);
+        }
+    }
+
+    /// Advance the replay by one tick, applying any inputs that are due, in
+    /// place of reading input from the mouse.
+    pub fn update_playback(&mut self, ctx: &mut Context) {
+        self.handle_playback_keys(ctx);
+        let millis = 1000.0 / tetra::time::get_tick_rate(ctx);
+        if let Some(player) = &mut self.player {
+            player.advance(millis);
+        }
+        self.apply_due_inputs(ctx);
+    }
+
+    /// Apply every input up to the current playback time, and update the
+    /// timer, cursor, and pressed tile to match.
+    fn apply_due_inputs(&mut self, ctx: &mut Context) {
+        while let Some(action) = self.player.as_mut().and_then(Player::next_due_action) {
+            self.apply_action(ctx, action);
+        }
+        if let Some(player) = &self.player {
+            self.seconds = player.get_seconds();
+            self.ui_state.tile_clicks.left_clicked_tile = player.get_pressed_tile();
+            let title = format!(
+                "Minesweeper replay ({}{}x)",
+                if player.paused { "paused, " } else { "" },
+                player.speed
+            );
+            if tetra::window::get_title(ctx) != title {
+                tetra::window::set_title(ctx, title);
+            }
+        }
+    }
+
+    /// Move playback to a particular time, replaying every input from the
+    /// beginning if moving backwards.
+    fn seek_playback(&mut self, ctx: &mut Context, time: f64) {
+        let current_time = match &self.player {
+            Some(player) => player.get_time(),
+            None => return,
+        };
+        if time < current_time {
+            self.restart_playback(ctx);
+        }
+        if let Some(player) = &mut self.player {
+            player.set_time(time);
+        }
+        self.apply_due_inputs(ctx);
+    }
+
+    fn handle_playback_keys(&mut self, ctx: &mut Context) {
+        let player = match &mut self.player {
+            Some(player) => player,
+            None => return,
+        };
+        if input::is_key_pressed(ctx, Key::Space) {
+            player.paused = !player.paused;
+        }
+        if input::is_key_pressed(ctx, Key::Up) {
+            player.speed = (player.speed * 2.0).min(MAX_SPEED);
+        }
+        if input::is_key_pressed(ctx, Key::Down) {
+            player.speed = (player.speed / 2.0).max(MIN_SPEED);
+        }
+        let time = player.get_time();
+        let next = player.get_next();
+        let target = if input::is_key_pressed(ctx, Key::Period) {
+            // Step forward to the next input.
+            player.paused = true;
+            Some(player.get_input_time(next))
+        } else if input::is_key_pressed(ctx, Key::Comma) {
+            // Step back to just after the input before the last one applied.
+            player.paused = true;
+            Some(match next {
+                0 | 1 => 0.0,
+                _ => player.get_input_time(next - 2),
+            })
+        } else if input::is_key_pressed(ctx, Key::Right) {
+            Some(time + SEEK_MILLIS)
+        } else if input::is_key_pressed(ctx, Key::Left) {
+            Some(time - SEEK_MILLIS)
+        } else if input::is_key_pressed(ctx, Key::Home) {
+            Some(0.0)
+        } else if input::is_key_pressed(ctx, Key::End) {
+            Some(player.get_end_time())
+        } else {
+            None
+        };
+        if let Some(target) = target {
+            self.seek_playback(ctx, target);
+        }
+    }
+}
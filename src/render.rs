@@ -1,27 +1,60 @@
+use ndarray::Array2;
 use std::convert::TryInto;
-use tetra::graphics::{self, texture::Texture, ui::NineSlice, DrawParams, Rectangle, Vec2};
+use tetra::graphics::{
+    self, texture::Texture, ui::NineSlice, Color, DrawParams, Font, Rectangle, Text, Vec2,
+};
 use tetra::input::MouseButton;
 use tetra::Context;
 
-use crate::board::{Tile, TileState};
+use minesweeper_engine::{probability, GameStage, InfinitePos, Tile, TileState, Topology};
+
+use crate::infinite::{InfiniteState, VIEW_SIZE};
 use crate::sprites::*;
 use crate::utils::*;
-use crate::GameStage;
 use crate::GameState;
 
 const NINESLICE_VERTICAL_EXTRA: f32 = 36.0;
 const OFFSET_FACE: (f32, f32) = (-13.0, 15.0);
 const OFFSET_MINES_COUNT: (f32, f32) = (15.0, 18.0);
+/// The right edge of the mine counter, and of the score on an infinite board,
+/// which has more digits.
+const MINES_COUNT_RIGHT: f32 = 15.0 + 13.0 * 3.0;
+const SCORE_RIGHT: f32 = 15.0 + 13.0 * 5.0;
+const LIVES_TOP: f32 = 24.0;
+/// The widest the number of lives can be, which is a heart and two digits,
+/// and the space to leave on either side of it.
+const LIVES_MAX_WIDTH: f32 = 24.0;
+const LIVES_MARGIN: f32 = 2.0;
+const SCORE_DIGITS: u32 = 5;
 const OFFSET_TIMER: (f32, f32) = (-(13.0 * 3.0 + 15.0), 18.0);
 const TILE_OFFSET_X: f32 = 15.0;
 const TILE_OFFSET_Y: f32 = 51.0;
-const TILE_SIZE: f32 = 16.0;
 const TOTAL_PADDING: (f32, f32) = (48.0 - 18.0, 84.0 - 18.0);
+const OVERLAY_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.75);
+const OVERLAY_TEXT_SIZE: f32 = 10.0;
+const OVERLAY_LINE_HEIGHT: f32 = 12.0;
+const CURSOR_COLOR: Color = Color::rgba(1.0, 0.0, 0.0, 0.8);
+const KEYBOARD_CURSOR_COLOR: Color = Color::rgba(0.0, 0.4, 1.0, 0.9);
+const HINT_SAFE_COLOR: Color = Color::rgba(0.0, 0.8, 0.0, 0.9);
+const HINT_MINE_COLOR: Color = Color::rgba(0.9, 0.0, 0.0, 0.9);
+const HINT_REASON_COLOR: Color = Color::rgba(1.0, 0.8, 0.0, 0.9);
+/// The size and color of numbers above 8, which have no sprite.
+const LARGE_NUMBER_TEXT_SIZE: f32 = 10.0;
+const LARGE_NUMBER_COLOR: Color = Color::rgb(0.4, 0.0, 0.4);
+const MINE_COUNT_COLOR: Color = Color::WHITE;
+const LIVES_TEXT_SIZE: f32 = 10.0;
+const LIVES_COLOR: Color = Color::rgb(0.8, 0.0, 0.0);
+const CURSOR_THICKNESS: f32 = 2.0;
 
 pub struct RenderState {
     borders_nineslice: NineSlice,
     spritemap: Texture,
+    /// A single white pixel, which can be scaled and tinted to draw rectangles.
+    pixel: Texture,
     face_rectangle: Rectangle,
+    /// Mine probabilities for the overlay, along with the tile states they
+    /// were computed from.
+    probabilities: Option<(Array2<TileState>, Array2<f64>)>,
 }
 
 impl RenderState {
@@ -34,25 +67,121 @@ impl RenderState {
                 Rectangle::new(16.0, 52.0, 16.0, 16.0),
             ),
             spritemap: Texture::new(ctx, "./resources/spritemap.png")?,
+            pixel: Texture::from_rgba(ctx, 1, 1, &[255, 255, 255, 255])?,
             face_rectangle: Rectangle::new(0.0, 0.0, 0.0, 0.0),
+            probabilities: None,
         })
     }
+
+    /// Draw the borders around the board and the counters, stretched to fill
+    /// the window.
+    fn draw_borders(&mut self, ctx: &mut Context) {
+        self.borders_nineslice.set_size(
+            graphics::get_internal_width(ctx) as f32,
+            graphics::get_internal_height(ctx) as f32 + NINESLICE_VERTICAL_EXTRA,
+        );
+        graphics::draw(ctx, &self.borders_nineslice, Vec2::new(0.0, 0.0));
+    }
+
+    /// Draw the last `digits` digits of a number on a seven-segment display.
+    fn draw_7seg(&self, ctx: &mut Context, mut position: Vec2, number: usize, digits: u32) {
+        for i in (0..digits).rev() {
+            graphics::draw(
+                ctx,
+                &self.spritemap,
+                DrawParams::new()
+                    .position(position)
+                    .clip(get_7seg_sprite_clip((number / 10_usize.pow(i)) % 10)),
+            );
+            position += Vec2::new(13.0, 0.0);
+        }
+    }
+
+    /// Draw the face centered at the top of the window.
+    fn draw_face(&mut self, ctx: &mut Context, sprite: FaceSprite) {
+        let position = self.place_face(ctx);
+        graphics::draw(
+            ctx,
+            &self.spritemap,
+            DrawParams::new().position(position).clip(sprite.into()),
+        )
+    }
+
+    /// Move the face to the center of the top of the window, which may have
+    /// changed size, and return its top left corner.
+    fn place_face(&mut self, ctx: &mut Context) -> Vec2 {
+        let x_center = tetra::graphics::get_internal_width(ctx) as f32 / 2.0;
+        let position = Vec2::new(x_center, 0.0) + vec2_from_tuple(&OFFSET_FACE);
+        self.face_rectangle = Rectangle::new(position[0], position[1], 26.0, 26.0);
+        position
+    }
+
+    /// Draw the number of lives left, centered between the counter on the
+    /// left (which ends at `counter_right`) and the face.
+    fn draw_lives(&self, ctx: &mut Context, lives_left: usize, counter_right: f32) {
+        let text = Text::new(
+            format!("\u{2665}{}", lives_left),
+            Font::default(),
+            LIVES_TEXT_SIZE,
+        );
+        let width = text.get_bounds(ctx).map_or(0.0, |bounds| bounds.width);
+        let face_left = tetra::graphics::get_internal_width(ctx) as f32 / 2.0 + OFFSET_FACE.0;
+        let x = ((counter_right + face_left - width) / 2.0).round();
+        graphics::draw(
+            ctx,
+            &text,
+            DrawParams::new()
+                .position(Vec2::new(x, LIVES_TOP))
+                .color(LIVES_COLOR),
+        );
+    }
+
+    pub fn is_mouse_on_face(&self, ctx: &mut Context) -> bool {
+        let rect = self.face_rectangle;
+        let mouse_pos = tetra::input::get_mouse_position(ctx);
+        let (mouse_x, mouse_y) = (mouse_pos.x, mouse_pos.y);
+        rect.x <= mouse_x
+            && mouse_x < rect.x + rect.width
+            && rect.y <= mouse_y
+            && mouse_y < rect.y + rect.height
+    }
 }
 
 impl GameState {
-    pub fn get_internal_window_size(board_size: &(usize, usize)) -> (i32, i32) {
-        let board_size = vec2_from_reverse_usize_tuple(board_size);
-        i32_tuple_from_vec2(&(board_size * TILE_SIZE + vec2_from_tuple(&TOTAL_PADDING)))
+    /// Return the size of the window before scaling, which is widened on a
+    /// narrow board if the game has more than one life, so that the number of
+    /// lives fits between the mine counter and the face.
+    pub fn get_internal_window_size(
+        board_size: &(usize, usize),
+        topology: &Topology,
+        lives: usize,
+    ) -> (i32, i32) {
+        let board_size = get_board_display_size(board_size, topology);
+        let extra_width = Vec2::new(get_lives_extra_width(board_size.x, lives), 0.0);
+        i32_tuple_from_vec2(&(board_size + extra_width + vec2_from_tuple(&TOTAL_PADDING)))
     }
 
-    pub fn get_window_size(board_size: &(usize, usize), scaling_factor: usize) -> (i32, i32) {
-        let (w, h) = GameState::get_internal_window_size(board_size);
+    pub fn get_window_size(
+        board_size: &(usize, usize),
+        topology: &Topology,
+        lives: usize,
+        scaling_factor: usize,
+    ) -> (i32, i32) {
+        let (w, h) = GameState::get_internal_window_size(board_size, topology, lives);
         (w * scaling_factor as i32, h * scaling_factor as i32)
     }
 
     pub fn reset_window_size(&self, ctx: &mut Context) {
-        let internal_window_size = GameState::get_internal_window_size(self.board.get_size());
-        let window_size = GameState::get_window_size(self.board.get_size(), self.scale_factor);
+        let board = self.game.get_board();
+        let lives = self.game.get_rules().lives;
+        let internal_window_size =
+            GameState::get_internal_window_size(board.get_size(), board.get_topology(), lives);
+        let window_size = GameState::get_window_size(
+            board.get_size(),
+            board.get_topology(),
+            lives,
+            self.scale_factor,
+        );
         if internal_window_size != graphics::get_internal_size(ctx) {
             graphics::set_internal_size(ctx, internal_window_size.0, internal_window_size.1);
         }
@@ -62,103 +191,164 @@ impl GameState {
     }
 
     pub fn draw_borders(&mut self, ctx: &mut Context) {
-        self.render_state.borders_nineslice.set_size(
-            graphics::get_internal_width(ctx) as f32,
-            graphics::get_internal_height(ctx) as f32 + NINESLICE_VERTICAL_EXTRA,
-        );
-        graphics::draw(
-            ctx,
-            &self.render_state.borders_nineslice,
-            Vec2::new(0.0, 0.0),
-        );
+        self.render_state.draw_borders(ctx);
     }
 
-    pub fn draw_tiles(&self, ctx: &mut Context) {
+    pub fn draw_tiles(&mut self, ctx: &mut Context) {
+        let mut probabilities = None;
+        if self.ui_state.show_probabilities
+            && self.game.get_stage() != GameStage::Complete
+            && self.game.get_board().get_mines_per_tile() == 1
+        {
+            self.update_probabilities();
+            probabilities = self.render_state.probabilities.as_ref().map(|(_, p)| p);
+        }
         let mut clicked_tile: Option<(usize, usize)> = None;
-        if let GameStage::Pre | GameStage::Playing = self.stage {
-            if tetra::input::is_mouse_button_down(ctx, tetra::input::MouseButton::Left) {
-                clicked_tile = self.get_tile_at_cursor(ctx);
-            }
+        if let GameStage::Pre | GameStage::Playing = self.game.get_stage() {
+            clicked_tile = self.ui_state.tile_clicks.left_clicked_tile;
         }
-        for (tile_pos, tilestate) in self.board.get_tilestates().indexed_iter() {
-            let tile = self.board.get_tiles()[tile_pos];
-            let tile_sprite = match tilestate {
-                TileState::Hidden => {
-                    if self.stage == GameStage::Exploded && tile == Tile::Mine {
-                        TileSprite::Mine
-                    } else if clicked_tile == Some(tile_pos) {
-                        TileSprite::HiddenClick
-                    } else {
-                        TileSprite::Hidden
-                    }
-                }
-                TileState::Flagged => {
-                    if self.stage == GameStage::Exploded && tile != Tile::Mine {
-                        TileSprite::IncorrectFlag
-                    } else {
-                        TileSprite::Flagged
-                    }
-                }
-                TileState::QuestionMark => {
-                    if self.stage == GameStage::Exploded && tile == Tile::Mine {
-                        TileSprite::MineExploded
-                    } else if clicked_tile == Some(tile_pos) {
-                        TileSprite::QuestionMarkClick
-                    } else {
-                        TileSprite::QuestionMark
-                    }
+        let topology = self.game.get_board().get_topology();
+        for (tile_pos, tilestate) in self.game.get_board().get_tilestates().indexed_iter() {
+            let tile = self.game.get_board().get_tiles()[tile_pos];
+            let tile_sprite = get_tile_sprite(
+                tile,
+                *tilestate,
+                self.game.get_stage(),
+                clicked_tile == Some(tile_pos),
+            );
+            let shows_mine = matches!(tile_sprite, TileSprite::Mine | TileSprite::MineExploded);
+            let color = match probabilities {
+                Some(probabilities) if *tilestate != TileState::Uncovered => {
+                    probability_tint(probabilities[tile_pos])
                 }
-                TileState::Uncovered => match tile {
-                    Tile::Mine => TileSprite::MineExploded,
-                    Tile::Safe(0) => TileSprite::Safe0,
-                    Tile::Safe(1) => TileSprite::Safe1,
-                    Tile::Safe(2) => TileSprite::Safe2,
-                    Tile::Safe(3) => TileSprite::Safe3,
-                    Tile::Safe(4) => TileSprite::Safe4,
-                    Tile::Safe(5) => TileSprite::Safe5,
-                    Tile::Safe(6) => TileSprite::Safe6,
-                    Tile::Safe(7) => TileSprite::Safe7,
-                    Tile::Safe(8) => TileSprite::Safe8,
-                    _ => TileSprite::QuestionMark,
-                },
+                _ => Color::WHITE,
             };
             graphics::draw(
                 ctx,
                 &self.render_state.spritemap,
                 DrawParams::new()
                     .position(vec2_from_tuple(&self.get_tile_display_pos(tile_pos)))
-                    .clip(tile_sprite.into()),
-            )
+                    .clip(get_tile_clip(tile_sprite, topology))
+                    .color(color),
+            );
+            match (tilestate, tile) {
+                (TileState::Uncovered, Tile::Safe(n)) if n > 8 => {
+                    self.draw_large_number(ctx, tile_pos, n, LARGE_NUMBER_COLOR);
+                }
+                // Show how many mines there are on a tile that holds more than
+                // one.
+                (_, Tile::Mine(n)) if n > 1 && shows_mine => {
+                    self.draw_large_number(ctx, tile_pos, n, MINE_COUNT_COLOR);
+                }
+                _ => (),
+            }
+        }
+        if let Some(hint) = &self.ui_state.hint {
+            for &pos in &hint.reasons {
+                let top_left = vec2_from_tuple(&self.get_tile_display_pos(pos));
+                self.draw_outline(ctx, top_left, HINT_REASON_COLOR);
+            }
+            let color = if hint.is_mine {
+                HINT_MINE_COLOR
+            } else {
+                HINT_SAFE_COLOR
+            };
+            let top_left = vec2_from_tuple(&self.get_tile_display_pos(hint.pos));
+            self.draw_outline(ctx, top_left, color);
+        }
+        if let Some(pos) = self.ui_state.keyboard_cursor {
+            let top_left = vec2_from_tuple(&self.get_tile_display_pos(pos));
+            self.draw_outline(ctx, top_left, KEYBOARD_CURSOR_COLOR);
+        }
+    }
+
+    /// Draw a number that has no sprite, centered on a tile.
+    fn draw_large_number(&self, ctx: &mut Context, pos: (usize, usize), n: u8, color: Color) {
+        let text = Text::new(n.to_string(), Font::default(), LARGE_NUMBER_TEXT_SIZE);
+        let (width, height) = text
+            .get_bounds(ctx)
+            .map_or((0.0, 0.0), |bounds| (bounds.width, bounds.height));
+        let top_left = vec2_from_tuple(&self.get_tile_display_pos(pos));
+        let offset = (Vec2::new(TILE_SIZE, TILE_SIZE) - Vec2::new(width, height)) / 2.0;
+        graphics::draw(
+            ctx,
+            &text,
+            DrawParams::new()
+                .position(Vec2::new(
+                    (top_left.x + offset.x).round(),
+                    (top_left.y + offset.y).round(),
+                ))
+                .color(color),
+        );
+    }
+
+    /// Recompute the probability that each tile is a mine, if the board has
+    /// changed since the last time.
+    fn update_probabilities(&mut self) {
+        let tilestates = self.game.get_board().get_tilestates();
+        let up_to_date = match &self.render_state.probabilities {
+            Some((cached_tilestates, _)) => cached_tilestates == tilestates,
+            None => false,
+        };
+        if !up_to_date {
+            self.render_state.probabilities = Some((
+                tilestates.clone(),
+                probability::mine_probabilities(self.game.get_board()),
+            ));
         }
     }
 
     fn get_tile_display_pos(&self, (y, x): (usize, usize)) -> (f32, f32) {
+        let topology = self.game.get_board().get_topology();
         (
-            TILE_SIZE * x as f32 + TILE_OFFSET_X,
-            TILE_SIZE * y as f32 + TILE_OFFSET_Y,
+            TILE_SIZE * x as f32 + get_row_offset(topology, y) + self.get_board_left(),
+            get_row_height(topology) * y as f32 + TILE_OFFSET_Y,
         )
     }
 
     pub fn get_tile_at_cursor(&self, ctx: &Context) -> Option<(usize, usize)> {
         let mouse_pos = tetra::input::get_mouse_position(ctx);
-        let x = ((mouse_pos[0] - TILE_OFFSET_X) / TILE_SIZE).floor() as usize;
-        let y = ((mouse_pos[1] - TILE_OFFSET_Y) / TILE_SIZE).floor() as usize;
-        let &(max_y, max_x) = self.board.get_size();
-        if y < max_y && x < max_x {
-            Some((y, x))
-        } else {
-            None
-        }
+        let board = self.game.get_board();
+        get_tile_at_point(
+            board.get_size(),
+            board.get_topology(),
+            (
+                mouse_pos[0] - self.get_board_left(),
+                mouse_pos[1] - TILE_OFFSET_Y,
+            ),
+        )
+    }
+
+    /// Return the left edge of the board on screen, which is centered if the
+    /// window was widened to fit the number of lives.
+    fn get_board_left(&self) -> f32 {
+        let board = self.game.get_board();
+        let width = get_board_display_size(board.get_size(), board.get_topology()).x;
+        TILE_OFFSET_X + get_lives_extra_width(width, self.game.get_rules().lives) / 2.0
     }
 
     pub fn draw_mine_counter(&self, ctx: &mut Context) {
         self.draw_triple_7seg(
             ctx,
             vec2_from_tuple(&OFFSET_MINES_COUNT),
-            self.board.get_flags_left().try_into().unwrap_or(0),
+            self.game
+                .get_board()
+                .get_flags_left()
+                .try_into()
+                .unwrap_or(0),
         );
     }
 
+    /// Draw the number of lives left between the mine counter and the face, if
+    /// the game started with more than one.
+    pub fn draw_lives(&self, ctx: &mut Context) {
+        if self.game.get_rules().lives <= 1 {
+            return;
+        }
+        self.render_state
+            .draw_lives(ctx, self.game.get_lives_left(), MINES_COUNT_RIGHT);
+    }
+
     pub fn draw_timer(&self, ctx: &mut Context) {
         self.draw_triple_7seg(
             ctx,
@@ -168,56 +358,325 @@ impl GameState {
         );
     }
 
-    fn draw_triple_7seg(&self, ctx: &mut Context, mut position: Vec2, mut number: usize) {
-        for _ in 0..3 {
-            graphics::draw(
-                ctx,
-                &self.render_state.spritemap,
-                DrawParams::new()
-                    .position(position)
-                    .clip(get_7seg_sprite_clip((number / 100) % 10)),
-            );
-            number *= 10;
-            position += Vec2::new(13.0, 0.0);
-        }
+    fn draw_triple_7seg(&self, ctx: &mut Context, position: Vec2, number: usize) {
+        self.render_state.draw_7seg(ctx, position, number, 3);
     }
 
     pub fn draw_face(&mut self, ctx: &mut Context) {
-        let x_center = tetra::graphics::get_internal_width(ctx) as f32 / 2.0;
-        let position = Vec2::new(x_center, 0.0) + vec2_from_tuple(&OFFSET_FACE);
-        self.render_state.face_rectangle = Rectangle::new(position[0], position[1], 26.0, 26.0);
+        self.render_state.place_face(ctx);
         let sprite = if self.is_mouse_on_face(ctx)
             && tetra::input::is_mouse_button_down(ctx, MouseButton::Left)
             && self.ui_state.face_clicked
         {
             FaceSprite::HappyPressed
         } else {
-            match self.stage {
-                GameStage::Pre | GameStage::Playing => match self.ui_state.left_clicked_tile {
-                    None => FaceSprite::Happy,
-                    Some(pos) => match self.board.get_tilestates()[pos] {
-                        TileState::Hidden | TileState::QuestionMark => FaceSprite::Scared,
-                        _ => FaceSprite::Happy,
-                    },
-                },
+            match self.game.get_stage() {
+                GameStage::Pre | GameStage::Playing => {
+                    match self.ui_state.tile_clicks.left_clicked_tile {
+                        None => FaceSprite::Happy,
+                        Some(pos) => match self.game.get_board().get_tilestates()[pos] {
+                            TileState::Hidden | TileState::QuestionMark => FaceSprite::Scared,
+                            _ => FaceSprite::Happy,
+                        },
+                    }
+                }
                 GameStage::Exploded => FaceSprite::Dead,
                 GameStage::Complete => FaceSprite::Cool,
             }
         };
+        self.render_state.draw_face(ctx, sprite);
+    }
+
+    pub fn is_mouse_on_face(&self, ctx: &mut Context) -> bool {
+        self.render_state.is_mouse_on_face(ctx)
+    }
+
+    /// Draw the position of the cursor recorded in a replay, if one is being
+    /// played back.
+    pub fn draw_replay_cursor(&self, ctx: &mut Context) {
+        if let Some((y, x)) = self.player.as_ref().and_then(|player| player.get_cursor()) {
+            let (left, top) = self.get_tile_display_pos((0, 0));
+            // The rows of a hexagonal board are closer together than the
+            // tiles are tall.
+            let row_height = get_row_height(self.game.get_board().get_topology());
+            let top_left = Vec2::new(left + (x - 0.5) * TILE_SIZE, top + (y - 0.5) * row_height);
+            self.draw_outline(ctx, top_left, CURSOR_COLOR);
+        }
+    }
+
+    /// Draw an outline around a tile-sized square, or a hexagon on a
+    /// hexagonal board.
+    fn draw_outline(&self, ctx: &mut Context, top_left: Vec2, color: Color) {
+        if *self.game.get_board().get_topology() == Topology::Hex {
+            graphics::draw(
+                ctx,
+                &self.render_state.spritemap,
+                DrawParams::new()
+                    .position(top_left)
+                    .clip(get_hex_outline_clip())
+                    .color(color),
+            );
+            return;
+        }
+        let t = CURSOR_THICKNESS;
+        let edges = [
+            (Vec2::new(0.0, 0.0), Vec2::new(TILE_SIZE, t)),
+            (Vec2::new(0.0, TILE_SIZE - t), Vec2::new(TILE_SIZE, t)),
+            (Vec2::new(0.0, 0.0), Vec2::new(t, TILE_SIZE)),
+            (Vec2::new(TILE_SIZE - t, 0.0), Vec2::new(t, TILE_SIZE)),
+        ];
+        for &(offset, size) in &edges {
+            graphics::draw(
+                ctx,
+                &self.render_state.pixel,
+                DrawParams::new()
+                    .position(top_left + offset)
+                    .scale(size)
+                    .color(color),
+            );
+        }
+    }
+
+    /// Darken the board and draw the text of the current overlay on top of it,
+    /// if there is one.
+    pub fn draw_overlay(&self, ctx: &mut Context) {
+        let overlay = match &self.ui_state.overlay {
+            Some(overlay) => overlay,
+            None => return,
+        };
+        let (board_x, board_y) = self.get_tile_display_pos((0, 0));
+        let board = self.game.get_board();
+        let board_size = get_board_display_size(board.get_size(), board.get_topology());
         graphics::draw(
             ctx,
-            &self.render_state.spritemap,
-            DrawParams::new().position(position).clip(sprite.into()),
+            &self.render_state.pixel,
+            DrawParams::new()
+                .position(Vec2::new(board_x, board_y))
+                .scale(board_size)
+                .color(OVERLAY_COLOR),
+        );
+        let lines = overlay.get_text();
+        let center_x = board_x + board_size.x / 2.0;
+        let mut y = board_y + (board_size.y - OVERLAY_LINE_HEIGHT * lines.len() as f32) / 2.0;
+        for line in lines {
+            let text = Text::new(line, Font::default(), OVERLAY_TEXT_SIZE);
+            let width = text.get_bounds(ctx).map_or(0.0, |bounds| bounds.width);
+            graphics::draw(
+                ctx,
+                &text,
+                Vec2::new((center_x - width / 2.0).round(), y.round()),
+            );
+            y += OVERLAY_LINE_HEIGHT;
+        }
+    }
+}
+
+impl InfiniteState {
+    pub fn draw_borders(&mut self, ctx: &mut Context) {
+        self.render_state.draw_borders(ctx);
+    }
+
+    /// Draw the tiles in view. Tiles whose mines have not been placed yet are
+    /// drawn as hidden.
+    pub fn draw_tiles(&mut self, ctx: &mut Context) {
+        let stage = self.game.get_stage();
+        let clicked_tile = if self.game.is_over() {
+            None
+        } else {
+            self.tile_clicks.left_clicked_tile
+        };
+        for y in 0..VIEW_SIZE.0 as i64 {
+            for x in 0..VIEW_SIZE.1 as i64 {
+                let pos = (self.camera.0 + y, self.camera.1 + x);
+                let tile = self.game.get_tile(pos).unwrap_or(Tile::Safe(0));
+                let tile_sprite = get_tile_sprite(
+                    tile,
+                    self.game.get_tilestate(pos),
+                    stage,
+                    clicked_tile == Some(pos),
+                );
+                graphics::draw(
+                    ctx,
+                    &self.render_state.spritemap,
+                    DrawParams::new()
+                        .position(vec2_from_tuple(&self.get_tile_display_pos(pos)))
+                        .clip(tile_sprite.into()),
+                );
+            }
+        }
+    }
+
+    /// Return the top left corner of a tile on screen, relative to the tile in
+    /// the top left corner of the view.
+    fn get_tile_display_pos(&self, (y, x): InfinitePos) -> (f32, f32) {
+        (
+            TILE_SIZE * (x - self.camera.1) as f32 + TILE_OFFSET_X,
+            TILE_SIZE * (y - self.camera.0) as f32 + TILE_OFFSET_Y,
         )
     }
 
-    pub fn is_mouse_on_face(&self, ctx: &mut Context) -> bool {
-        let rect = self.render_state.face_rectangle;
+    pub fn get_tile_at_cursor(&self, ctx: &Context) -> Option<InfinitePos> {
         let mouse_pos = tetra::input::get_mouse_position(ctx);
-        let (mouse_x, mouse_y) = (mouse_pos.x, mouse_pos.y);
-        rect.x <= mouse_x
-            && mouse_x < rect.x + rect.width
-            && rect.y <= mouse_y
-            && mouse_y < rect.y + rect.height
+        let y = ((mouse_pos[1] - TILE_OFFSET_Y) / TILE_SIZE).floor();
+        let x = ((mouse_pos[0] - TILE_OFFSET_X) / TILE_SIZE).floor();
+        if 0.0 <= y && y < VIEW_SIZE.0 as f32 && 0.0 <= x && x < VIEW_SIZE.1 as f32 {
+            Some((self.camera.0 + y as i64, self.camera.1 + x as i64))
+        } else {
+            None
+        }
+    }
+
+    /// Draw the number of tiles cleared where the mine counter usually is.
+    pub fn draw_score(&self, ctx: &mut Context) {
+        self.render_state.draw_7seg(
+            ctx,
+            vec2_from_tuple(&OFFSET_MINES_COUNT),
+            self.game.get_cleared(),
+            SCORE_DIGITS,
+        );
+    }
+
+    /// Draw the number of lives left between the score and the face, if the
+    /// game started with more than one.
+    pub fn draw_lives(&self, ctx: &mut Context) {
+        if self.game.get_rules().lives <= 1 {
+            return;
+        }
+        self.render_state
+            .draw_lives(ctx, self.game.get_lives_left(), SCORE_RIGHT);
+    }
+
+    pub fn draw_timer(&self, ctx: &mut Context) {
+        self.render_state.draw_7seg(
+            ctx,
+            vec2_from_tuple(&OFFSET_TIMER)
+                + Vec2::new(tetra::graphics::get_internal_width(ctx) as f32, 0.0),
+            self.seconds,
+            3,
+        );
+    }
+
+    pub fn draw_face(&mut self, ctx: &mut Context) {
+        self.render_state.place_face(ctx);
+        let sprite = if self.render_state.is_mouse_on_face(ctx)
+            && tetra::input::is_mouse_button_down(ctx, MouseButton::Left)
+            && self.face_clicked
+        {
+            FaceSprite::HappyPressed
+        } else if self.game.is_over() {
+            FaceSprite::Dead
+        } else {
+            match self
+                .tile_clicks
+                .left_clicked_tile
+                .map(|pos| self.game.get_tilestate(pos))
+            {
+                Some(TileState::Hidden) | Some(TileState::QuestionMark) => FaceSprite::Scared,
+                _ => FaceSprite::Happy,
+            }
+        };
+        self.render_state.draw_face(ctx, sprite);
+    }
+}
+
+/// Return the size of the board on screen. A hexagonal board with shifted rows
+/// is half a tile wider, and its rows overlap.
+fn get_board_display_size(board_size: &(usize, usize), topology: &Topology) -> Vec2 {
+    let &(height, width) = board_size;
+    let mut size = Vec2::new(
+        width as f32 * TILE_SIZE,
+        height.saturating_sub(1) as f32 * get_row_height(topology) + TILE_SIZE,
+    );
+    if *topology == Topology::Hex && height > 1 {
+        size.x += TILE_SIZE / 2.0;
+    }
+    size
+}
+
+/// Return how much wider the window must be than the board and its padding so
+/// that the number of lives fits between the mine counter and the face, which
+/// is in the middle.
+fn get_lives_extra_width(board_display_width: f32, lives: usize) -> f32 {
+    if lives <= 1 {
+        return 0.0;
+    }
+    let face_left = MINES_COUNT_RIGHT + LIVES_MARGIN + LIVES_MAX_WIDTH + LIVES_MARGIN;
+    let min_width = 2.0 * (face_left - OFFSET_FACE.0);
+    (min_width - board_display_width - TOTAL_PADDING.0).max(0.0)
+}
+
+/// Return the clip of a tile sprite, which is cut to the shape of a hexagon on
+/// a hexagonal board.
+fn get_tile_clip(tile_sprite: TileSprite, topology: &Topology) -> Rectangle {
+    match topology {
+        Topology::Hex => tile_sprite.get_hex_clip(),
+        _ => tile_sprite.into(),
     }
 }
+
+/// Return the sprite for a tile, given whether the reveal button is held down
+/// on it.
+fn get_tile_sprite(
+    tile: Tile,
+    tilestate: TileState,
+    stage: GameStage,
+    clicked: bool,
+) -> TileSprite {
+    match tilestate {
+        TileState::Hidden => {
+            if stage == GameStage::Exploded && tile.is_mine() {
+                TileSprite::Mine
+            } else if clicked {
+                TileSprite::HiddenClick
+            } else {
+                TileSprite::Hidden
+            }
+        }
+        TileState::Flagged(n) => {
+            if stage == GameStage::Exploded && tile.get_mines() != n {
+                TileSprite::IncorrectFlag
+            } else {
+                match n {
+                    2 => TileSprite::Flagged2,
+                    3 => TileSprite::Flagged3,
+                    4 => TileSprite::Flagged4,
+                    5 => TileSprite::Flagged5,
+                    _ => TileSprite::Flagged,
+                }
+            }
+        }
+        TileState::QuestionMark => {
+            if stage == GameStage::Exploded && tile.is_mine() {
+                TileSprite::MineExploded
+            } else if clicked {
+                TileSprite::QuestionMarkClick
+            } else {
+                TileSprite::QuestionMark
+            }
+        }
+        TileState::Uncovered => match tile {
+            Tile::Mine(_) => TileSprite::MineExploded,
+            Tile::Safe(0) => TileSprite::Safe0,
+            Tile::Safe(1) => TileSprite::Safe1,
+            Tile::Safe(2) => TileSprite::Safe2,
+            Tile::Safe(3) => TileSprite::Safe3,
+            Tile::Safe(4) => TileSprite::Safe4,
+            Tile::Safe(5) => TileSprite::Safe5,
+            Tile::Safe(6) => TileSprite::Safe6,
+            Tile::Safe(7) => TileSprite::Safe7,
+            Tile::Safe(8) => TileSprite::Safe8,
+            // Larger numbers are drawn as text on top of a blank tile.
+            Tile::Safe(_) => TileSprite::Safe0,
+        },
+    }
+}
+
+/// Return a tint that shades a tile from green (certainly safe) through yellow
+/// to red (certainly a mine).
+fn probability_tint(probability: f64) -> Color {
+    let p = probability as f32;
+    let red = (2.0 * p).min(1.0);
+    let green = (2.0 * (1.0 - p)).min(1.0);
+    // Mix with white so that the tile underneath is still recognizable.
+    Color::rgb(0.5 + red / 2.0, 0.5 + green / 2.0, 0.5)
+}
@@ -0,0 +1,373 @@
+//! Recordings of every input applied to the board during a game.
+//!
+//! A replay is stored as JSON, and contains the final layout of the mines (so
+//! that it does not depend on how the first click rearranged them) along with
+//! each input and the time at which it happened.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use minesweeper_engine::{Action, Board, Difficulty, GameStage, Pos, Rules, Topology};
+
+use crate::cli;
+use crate::files;
+
+/// The version of the replay format. This must be incremented whenever the
+/// format changes in a way that older versions cannot read.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// The directory (within the data directory) where replays are kept.
+const REPLAY_DIR: &str = "replays";
+
+/// An input, along with the time at which it happened.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Input {
+    /// Milliseconds since the board was created.
+    pub time: u64,
+    pub action: Action,
+}
+
+/// A complete recording of a game.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub difficulty: Difficulty,
+    pub seed: u64,
+    /// The position of every mine on the board, after the first click.
+    pub mines: Vec<Pos>,
+    pub inputs: Vec<Input>,
+    /// The stage that the game was in when the replay was written.
+    pub stage: GameStage,
+    pub seconds: usize,
+    pub assisted: bool,
+    /// Whether right clicks cycled through question marks. Replays written
+    /// before this option existed always did.
+    #[serde(default = "default_question_marks")]
+    pub question_marks: bool,
+    /// The number of mines that could be uncovered before losing. Replays
+    /// written before this option existed always had one.
+    #[serde(default = "default_lives")]
+    pub lives: usize,
+}
+
+fn default_question_marks() -> bool {
+    true
+}
+
+fn default_lives() -> usize {
+    1
+}
+
+/// Records the inputs of a game as it is played.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    start: Instant,
+    /// When the game started, which is used to name the replay file.
+    start_time: SystemTime,
+    inputs: Vec<Input>,
+    /// The file that the replay was first written to, if it has been.
+    file_name: Option<String>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            start_time: SystemTime::now(),
+            inputs: vec![],
+            file_name: None,
+        }
+    }
+
+    /// Continue recording a game (such as one that was saved and resumed) from
+    /// inputs recorded earlier.
+    pub fn resume(inputs: Vec<Input>) -> Self {
+        let elapsed = Duration::from_millis(inputs.last().map_or(0, |input| input.time));
+        let now = Instant::now();
+        let now_time = SystemTime::now();
+        Self {
+            start: now.checked_sub(elapsed).unwrap_or(now),
+            start_time: now_time.checked_sub(elapsed).unwrap_or(now_time),
+            inputs,
+            file_name: None,
+        }
+    }
+
+    pub fn get_inputs(&self) -> &[Input] {
+        &self.inputs
+    }
+
+    /// Record an input that was just applied to the board.
+    pub fn record(&mut self, action: Action) {
+        let time = self.start.elapsed().as_millis() as u64;
+        self.inputs.push(Input { time, action });
+    }
+
+    /// Make a replay of the game so far.
+    pub fn finish(
+        &self,
+        board: &Board,
+        difficulty: &Difficulty,
+        stage: GameStage,
+        seconds: usize,
+        assisted: bool,
+        rules: Rules,
+    ) -> Replay {
+        // A tile with more than one mine on it is listed once for each mine.
+        let mines = board
+            .get_tiles()
+            .indexed_iter()
+            .flat_map(|(pos, tile)| (0..tile.get_mines()).map(move |_| pos))
+            .collect();
+        Replay {
+            version: REPLAY_VERSION,
+            difficulty: difficulty.clone(),
+            seed: board.get_seed(),
+            mines,
+            inputs: self.inputs.clone(),
+            stage,
+            seconds,
+            assisted,
+            question_marks: rules.question_marks,
+            lives: rules.lives,
+        }
+    }
+
+    /// Write a replay to a file named after the time that this game started,
+    /// replacing any replay written earlier in the same game. If another game
+    /// that started in the same second already has that file, a number is
+    /// added to the name.
+    pub fn save(&mut self, replay: &Replay) {
+        let start_time = self.start_time;
+        let file_name = self.file_name.get_or_insert_with(|| {
+            let timestamp = start_time
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs());
+            let mut file_name = format!("{}/{}.json", REPLAY_DIR, timestamp);
+            let mut n = 1;
+            while files::data_path(&file_name).exists() {
+                n += 1;
+                file_name = format!("{}/{}-{}.json", REPLAY_DIR, timestamp, n);
+            }
+            file_name
+        });
+        match files::write_json(file_name, replay) {
+            Ok(()) => println!("Saved replay to {}", files::data_path(file_name).display()),
+            Err(err) => println!("Could not save replay: {}", err),
+        }
+    }
+}
+
+/// Load a replay from a file.
+pub fn load(path: &Path) -> Result<Replay, String> {
+    let replay: Replay = match files::read_json_path(path) {
+        Ok(Some(replay)) => replay,
+        Ok(None) => return Err(format!("{} does not exist", path.display())),
+        Err(err) => return Err(format!("Could not read {}: {}", path.display(), err)),
+    };
+    if replay.version > REPLAY_VERSION {
+        return Err(format!(
+            "{} uses replay format version {}, but only versions up to {} are supported",
+            path.display(),
+            replay.version,
+            REPLAY_VERSION
+        ));
+    }
+    check(&replay).map_err(|msg| format!("{} is not a valid replay: {}", path.display(), msg))?;
+    Ok(replay)
+}
+
+/// Check that a replay (which may have been edited by hand) describes a board
+/// that can be played back, with every input on the board.
+fn check(replay: &Replay) -> Result<(), &'static str> {
+    // The same checks as for a new game with this difficulty.
+    replay.difficulty.new_game(replay.seed)?;
+    if cli::MAX_MINES_PER_TILE < replay.difficulty.mines_per_tile {
+        return Err("Tiles hold too many mines");
+    }
+    if !(1..=cli::MAX_LIVES).contains(&replay.lives) {
+        return Err("The number of lives is out of range");
+    }
+    make_board(replay)?;
+    let &(height, width) = &replay.difficulty.size;
+    let on_board = |input: &Input| match input.action.get_pos() {
+        Some((y, x)) => y < height && x < width,
+        None => true,
+    };
+    if !replay.inputs.iter().all(on_board) {
+        return Err("An input is outside the board");
+    }
+    Ok(())
+}
+
+/// Make a board with the mines of a replay, before any inputs.
+fn make_board(replay: &Replay) -> Result<Board, &'static str> {
+    Board::make_from_mines(
+        replay.difficulty.size,
+        replay.difficulty.topology.clone(),
+        replay.difficulty.wrap,
+        &replay.mines,
+        replay.difficulty.mines_per_tile,
+        replay.seed,
+    )
+}
+
+/// The number of milliseconds before a click that the tile is shown pressed
+/// during playback.
+const PRESS_MILLIS: f64 = 150.0;
+
+/// Plays back the inputs of a replay over time.
+#[derive(Clone, Debug)]
+pub struct Player {
+    replay: Replay,
+    /// The current playback time, in milliseconds since the board was created.
+    time: f64,
+    /// The index of the next input to apply.
+    next: usize,
+    pub paused: bool,
+    pub speed: f64,
+}
+
+impl Player {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            time: 0.0,
+            next: 0,
+            paused: false,
+            speed: 1.0,
+        }
+    }
+
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+
+    /// Return the index of the next input to apply.
+    pub fn get_next(&self) -> usize {
+        self.next
+    }
+
+    /// Return the time of the last input, which is when playback ends.
+    pub fn get_end_time(&self) -> f64 {
+        self.replay
+            .inputs
+            .last()
+            .map_or(0.0, |input| input.time as f64)
+    }
+
+    /// Return the time of a particular input.
+    pub fn get_input_time(&self, index: usize) -> f64 {
+        self.replay
+            .inputs
+            .get(index)
+            .map_or(0.0, |input| input.time as f64)
+    }
+
+    /// Make a board with the mines of the replay, before any inputs.
+    pub fn make_board(&self) -> Board {
+        // The replay was checked when it was loaded.
+        make_board(&self.replay).unwrap()
+    }
+
+    /// Move back to the beginning. The caller is responsible for resetting the
+    /// board.
+    pub fn rewind(&mut self) {
+        self.time = 0.0;
+        self.next = 0;
+    }
+
+    /// Move to a particular time. Moving backwards does not undo any inputs, so
+    /// the caller should rewind first.
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time.clamp(0.0, self.get_end_time());
+    }
+
+    /// Advance the playback time by some number of real milliseconds, unless
+    /// playback is paused.
+    pub fn advance(&mut self, millis: f64) {
+        if !self.paused {
+            self.set_time(self.time + millis * self.speed);
+        }
+    }
+
+    /// Return the next input if it is due to be applied, and move past it.
+    pub fn next_due_action(&mut self) -> Option<Action> {
+        let input = self.replay.inputs.get(self.next)?;
+        if input.time as f64 <= self.time {
+            self.next += 1;
+            Some(input.action)
+        } else {
+            None
+        }
+    }
+
+    /// Return the number of seconds that the game timer showed at the current
+    /// playback time. The timer starts at the first left click and stops at
+    /// the last input.
+    pub fn get_seconds(&self) -> usize {
+        let first_click = self
+            .replay
+            .inputs
+            .iter()
+            .find(|input| matches!(input.action, Action::LeftClick(_) | Action::Chord(_)));
+        match first_click {
+            Some(input) if input.time as f64 <= self.time => {
+                ((self.time - input.time as f64) / 1000.0) as usize
+            }
+            _ => 0,
+        }
+    }
+
+    /// Return the inputs before and after the current time that happened at a
+    /// particular tile.
+    fn get_surrounding_positional_inputs(&self) -> (Option<&Input>, Option<&Input>) {
+        let (before, after) = self.replay.inputs.split_at(self.next);
+        let has_pos = |input: &&Input| input.action.get_pos().is_some();
+        (
+            before.iter().rev().find(has_pos),
+            after.iter().find(has_pos),
+        )
+    }
+
+    /// Return the position of the cursor in tile coordinates (where the center
+    /// of the tile at (y, x) is at (y + 0.5, x + 0.5), plus another half a tile
+    /// to the right on odd rows of a hexagonal board), which moves smoothly
+    /// from one click to the next.
+    pub fn get_cursor(&self) -> Option<(f32, f32)> {
+        let hex = self.replay.difficulty.topology == Topology::Hex;
+        let center = |input: &Input| {
+            let (y, x) = input.action.get_pos().unwrap();
+            let row_offset = if hex && y % 2 == 1 { 0.5 } else { 0.0 };
+            (y as f32 + 0.5, x as f32 + 0.5 + row_offset)
+        };
+        match self.get_surrounding_positional_inputs() {
+            (Some(input), None) | (None, Some(input)) => Some(center(input)),
+            (Some(before), Some(after)) => {
+                let start = before.time as f64;
+                let end = (after.time as f64 - PRESS_MILLIS).max(start);
+                let t = if end > start {
+                    ((self.time - start) / (end - start)).clamp(0.0, 1.0) as f32
+                } else {
+                    1.0
+                };
+                let (from, to) = (center(before), center(after));
+                Some((from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t))
+            }
+            (None, None) => None,
+        }
+    }
+
+    /// Return the tile that is about to be left clicked, if any, so that it can
+    /// be shown pressed.
+    pub fn get_pressed_tile(&self) -> Option<Pos> {
+        match self.get_surrounding_positional_inputs().1 {
+            Some(input) if input.time as f64 - self.time <= PRESS_MILLIS => match input.action {
+                Action::LeftClick(pos) | Action::Chord(pos) => Some(pos),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
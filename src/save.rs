@@ -0,0 +1,58 @@
+use minesweeper_engine::{Board, Difficulty, GameStage};
+use serde::{Deserialize, Serialize};
+
+use crate::files;
+use crate::replay::Input;
+
+const SAVE_FILE_NAME: &str = "save.json";
+
+/// An in-progress game, saved when the window closes so that it can be resumed
+/// on the next launch.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub board: Board,
+    pub stage: GameStage,
+    pub seconds: usize,
+    pub difficulty: Difficulty,
+    pub assisted: bool,
+    /// The inputs recorded so far, so that the replay of the game is complete.
+    #[serde(default)]
+    pub inputs: Vec<Input>,
+    /// The number of mines that could be uncovered before losing, which
+    /// carries over even if the game is resumed without the same option.
+    #[serde(default = "default_lives")]
+    pub lives: usize,
+    /// Whether the board was generated with `--no-guess`, so that the game is
+    /// still kept apart in the best times and statistics after resuming it.
+    #[serde(default)]
+    pub no_guess: bool,
+}
+
+fn default_lives() -> usize {
+    1
+}
+
+/// Load the saved game, if there is one.
+pub fn load() -> Option<SavedGame> {
+    match files::read_json(SAVE_FILE_NAME) {
+        Ok(saved_game) => saved_game,
+        Err(err) => {
+            println!("Could not load saved game: {}", err);
+            None
+        }
+    }
+}
+
+/// Save a game, replacing any game that was saved before.
+pub fn store(saved_game: &SavedGame) {
+    if let Err(err) = files::write_json(SAVE_FILE_NAME, saved_game) {
+        println!("Could not save game: {}", err);
+    }
+}
+
+/// Delete the saved game, if there is one.
+pub fn delete() {
+    if let Err(err) = files::remove(SAVE_FILE_NAME) {
+        println!("Could not delete saved game: {}", err);
+    }
+}
@@ -0,0 +1,100 @@
+use minesweeper_engine::Difficulty;
+use serde::{Deserialize, Serialize};
+
+use crate::files;
+
+const SCORES_FILE_NAME: &str = "scores.json";
+
+/// The number of best times to keep for each difficulty.
+pub const MAX_SCORES: usize = 5;
+
+/// A single best time.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub seconds: usize,
+}
+
+/// The best times for one difficulty, fastest first.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Table {
+    difficulty: Difficulty,
+    /// Whether the boards were generated with `--no-guess`, which makes them
+    /// easier, so they get a table of their own.
+    #[serde(default)]
+    no_guess: bool,
+    scores: Vec<HighScore>,
+}
+
+/// The best times for every difficulty that has been won.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    tables: Vec<Table>,
+    /// The name that was entered most recently, which is suggested for the
+    /// next high score.
+    #[serde(default)]
+    pub last_name: String,
+}
+
+impl HighScores {
+    /// Return the best times for a difficulty, fastest first.
+    pub fn get(&self, difficulty: &Difficulty, no_guess: bool) -> &[HighScore] {
+        self.tables
+            .iter()
+            .find(|table| table.difficulty == *difficulty && table.no_guess == no_guess)
+            .map_or(&[], |table| &table.scores)
+    }
+
+    /// Return whether a time would make it into the table for a difficulty.
+    pub fn qualifies(&self, difficulty: &Difficulty, no_guess: bool, seconds: usize) -> bool {
+        let scores = self.get(difficulty, no_guess);
+        scores.len() < MAX_SCORES || scores.iter().any(|score| seconds < score.seconds)
+    }
+
+    /// Add a time to the table for a difficulty, dropping the slowest time if
+    /// the table is full. Times that tie with an existing one are placed after
+    /// it.
+    pub fn add(&mut self, difficulty: &Difficulty, no_guess: bool, score: HighScore) {
+        self.last_name = score.name.clone();
+        let index = match self
+            .tables
+            .iter()
+            .position(|table| table.difficulty == *difficulty && table.no_guess == no_guess)
+        {
+            Some(index) => index,
+            None => {
+                self.tables.push(Table {
+                    difficulty: difficulty.clone(),
+                    no_guess,
+                    scores: vec![],
+                });
+                self.tables.len() - 1
+            }
+        };
+        let scores = &mut self.tables[index].scores;
+        let rank = scores
+            .iter()
+            .position(|other| score.seconds < other.seconds)
+            .unwrap_or(scores.len());
+        scores.insert(rank, score);
+        scores.truncate(MAX_SCORES);
+    }
+}
+
+/// Load the high scores, or return empty tables if there are none.
+pub fn load() -> HighScores {
+    match files::read_json(SCORES_FILE_NAME) {
+        Ok(high_scores) => high_scores.unwrap_or_default(),
+        Err(err) => {
+            println!("Could not load high scores: {}", err);
+            HighScores::default()
+        }
+    }
+}
+
+/// Save the high scores, replacing the ones that were saved before.
+pub fn store(high_scores: &HighScores) {
+    if let Err(err) = files::write_json(SCORES_FILE_NAME, high_scores) {
+        println!("Could not save high scores: {}", err);
+    }
+}
@@ -4,6 +4,10 @@ pub enum TileSprite {
     Hidden,
     HiddenClick,
     Flagged,
+    Flagged2,
+    Flagged3,
+    Flagged4,
+    Flagged5,
     QuestionMark,
     QuestionMarkClick,
     Mine,
@@ -20,12 +24,19 @@ pub enum TileSprite {
     Safe8,
 }
 
-impl Into<Rectangle> for TileSprite {
-    fn into(self) -> Rectangle {
-        let (u, v) = match self {
+/// The distance from each square tile sprite down to its hexagonal version.
+const HEX_TILE_SPRITE_OFFSET: f32 = 77.0;
+
+impl TileSprite {
+    fn get_uv(&self) -> (f32, f32) {
+        match self {
             TileSprite::Hidden => (2.0, 53.0),
             TileSprite::HiddenClick => (19.0, 53.0),
             TileSprite::Flagged => (36.0, 53.0),
+            TileSprite::Flagged2 => (2.0, 112.0),
+            TileSprite::Flagged3 => (19.0, 112.0),
+            TileSprite::Flagged4 => (36.0, 112.0),
+            TileSprite::Flagged5 => (53.0, 112.0),
             TileSprite::QuestionMark => (53.0, 53.0),
             TileSprite::QuestionMarkClick => (70.0, 53.0),
             TileSprite::Mine => (87.0, 53.0),
@@ -40,11 +51,30 @@ impl Into<Rectangle> for TileSprite {
             TileSprite::Safe6 => (87.0, 70.0),
             TileSprite::Safe7 => (104.0, 70.0),
             TileSprite::Safe8 => (121.0, 70.0),
-        };
+        }
+    }
+
+    /// Return the clip of the sprite for a hexagonal tile, which is cut to
+    /// the shape of a hexagon with a point at the top and bottom.
+    pub fn get_hex_clip(&self) -> Rectangle {
+        let (u, v) = self.get_uv();
+        Rectangle::new(u, v + HEX_TILE_SPRITE_OFFSET, 16.0, 16.0)
+    }
+}
+
+impl Into<Rectangle> for TileSprite {
+    fn into(self) -> Rectangle {
+        let (u, v) = self.get_uv();
         Rectangle::new(u, v, 16.0, 16.0)
     }
 }
 
+/// Return the clip of a white outline of a hexagonal tile, which is tinted
+/// when drawn to highlight a tile.
+pub fn get_hex_outline_clip() -> Rectangle {
+    Rectangle::new(70.0, 112.0 + HEX_TILE_SPRITE_OFFSET, 16.0, 16.0)
+}
+
 pub fn get_7seg_sprite_clip(digit: usize) -> Rectangle {
     let sprite_id = (digit + 9) % 10;
     Rectangle::new(sprite_id as f32 * 14.0 + 2.0, 2.0, 13.0, 23.0)
@@ -0,0 +1,22 @@
+use minesweeper_engine::stats::{get_3bv, Clicks};
+use minesweeper_engine::Board;
+
+/// Print the efficiency statistics for a game that just ended.
+pub fn print_report(board: &Board, clicks: &Clicks, seconds: usize) {
+    let (solved, total) = get_3bv(board);
+    // The timer only counts whole seconds, so avoid dividing by zero on very
+    // fast games.
+    let seconds = seconds.max(1) as f64;
+    println!("3BV: {}/{}", solved, total);
+    println!("3BV/s: {:.2}", solved as f64 / seconds);
+    if clicks.total() > 0 {
+        println!("IOE: {:.2}", solved as f64 / clicks.total() as f64);
+    }
+    println!(
+        "Clicks: {} ({} left, {} right, {} chord)",
+        clicks.total(),
+        clicks.left,
+        clicks.right,
+        clicks.chord
+    );
+}
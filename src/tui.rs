@@ -0,0 +1,483 @@
+//! A front-end that draws the board in a terminal, for playing without a
+//! display (such as over SSH).
+//!
+//! The terminal is put into raw mode and drawn with ANSI escape sequences.
+//! Mouse input uses the SGR mouse reporting mode supported by most terminals.
+
+use minesweeper_engine::{
+    board, stats, Action, Board, Difficulty, Game, GameStage, Pos, Rules, Tile, TileState, Topology,
+};
+use std::io::{self, Read, Write};
+use std::panic;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Instant;
+
+use crate::bindings::{self, Bindings, Button};
+use crate::cli::Params;
+use crate::utils;
+
+/// The row of the terminal (starting from 1) where the board starts.
+const BOARD_ROW: usize = 3;
+/// The column of the terminal (starting from 1) where the board starts.
+const BOARD_COLUMN: usize = 1;
+
+const ENTER_SCREEN: &str = "\x1b[?1049h\x1b[?25l\x1b[?1000h\x1b[?1006h";
+const LEAVE_SCREEN: &str = "\x1b[?1006l\x1b[?1000l\x1b[?25h\x1b[?1049l";
+const RESET: &str = "\x1b[0m";
+
+/// A single input read from the terminal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Input {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    /// A mouse button pressed at a row and column of the terminal (starting
+    /// from 1).
+    Mouse(Button, usize, usize),
+}
+
+/// Puts the terminal into raw mode on the alternate screen, and restores it
+/// when dropped (including when unwinding from a panic).
+struct Terminal {
+    original: libc::termios,
+}
+
+impl Terminal {
+    fn enable() -> io::Result<Self> {
+        let original = unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            // Wait at most a tenth of a second for input, so that the timer
+            // keeps running.
+            raw.c_cc[libc::VMIN] = 0;
+            raw.c_cc[libc::VTIME] = 1;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            original
+        };
+        let terminal = Self { original };
+        let mut stdout = io::stdout();
+        write!(stdout, "{}", ENTER_SCREEN)?;
+        stdout.flush()?;
+        Ok(terminal)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        restore(&self.original);
+    }
+}
+
+/// Leave the alternate screen and put the terminal back into the mode it was
+/// in before. Doing this more than once is harmless.
+fn restore(original: &libc::termios) {
+    let mut stdout = io::stdout();
+    let _ = write!(stdout, "{}", LEAVE_SCREEN);
+    let _ = stdout.flush();
+    unsafe {
+        libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, original);
+    }
+}
+
+struct TuiState {
+    game: Game,
+    difficulty: Difficulty,
+    rules: Rules,
+    bindings: Bindings,
+    /// The seed to use for every board, if one was given on the command line.
+    seed: Option<u64>,
+    cursor: Pos,
+    /// When the first tile was uncovered.
+    start: Option<Instant>,
+    /// The time that the game took, once it is over.
+    final_seconds: Option<usize>,
+    /// The first click and the board being generated for it on another
+    /// thread, while waiting for a board that can be solved without guessing.
+    generating: Option<(Action, Receiver<(Board, bool)>)>,
+    /// Bytes read from the terminal that have not been parsed yet.
+    pending: Vec<u8>,
+    quit: bool,
+}
+
+/// Play in the terminal until the player quits.
+pub fn run(params: Params) -> io::Result<()> {
+    let mut state = TuiState {
+        game: Game::new(Board::make_empty((1, 1)), params.get_rules()),
+        difficulty: params.difficulty.clone(),
+        rules: params.get_rules(),
+        bindings: bindings::load_without_writing(),
+        seed: params.seed,
+        cursor: (0, 0),
+        start: None,
+        final_seconds: None,
+        generating: None,
+        pending: vec![],
+        quit: false,
+    };
+    state
+        .new_game()
+        .map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?;
+
+    let terminal = Terminal::enable()?;
+    // The terminal is also restored when unwinding, but a panic message is
+    // printed before that, so it would be lost on the alternate screen.
+    let original = terminal.original;
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore(&original);
+        default_hook(info);
+    }));
+    let result = state.run(&mut io::stdout());
+    drop(terminal);
+    // Put back the default hook.
+    let _ = panic::take_hook();
+    result
+}
+
+impl TuiState {
+    fn run(&mut self, stdout: &mut io::Stdout) -> io::Result<()> {
+        let mut stdin = io::stdin();
+        let mut buffer = [0; 64];
+        while !self.quit {
+            self.draw(stdout)?;
+            let n = stdin.read(&mut buffer)?;
+            self.pending.extend_from_slice(&buffer[..n]);
+            while let Some(input) = self.next_input() {
+                self.handle_input(input);
+            }
+            self.poll_generating();
+        }
+        Ok(())
+    }
+
+    /// Apply the first click once the board being generated for it is ready.
+    fn poll_generating(&mut self) {
+        let (action, receiver) = match self.generating.take() {
+            Some(generating) => generating,
+            None => return,
+        };
+        match receiver.try_recv() {
+            Ok((board, solvable)) => {
+                self.game.set_start_board(board, solvable);
+                self.apply(action);
+            }
+            Err(TryRecvError::Empty) => self.generating = Some((action, receiver)),
+            // The thread panicked, so the click is dropped.
+            Err(TryRecvError::Disconnected) => (),
+        }
+    }
+
+    /// Start a new game with the same difficulty.
+    fn new_game(&mut self) -> Result<(), &'static str> {
+        let seed = self.seed.unwrap_or_else(board::random_seed);
+        let board = self.difficulty.new_game(seed)?;
+        let &(height, width) = board.get_size();
+        self.game = Game::new(board, self.rules);
+        self.cursor = (height / 2, width / 2);
+        self.start = None;
+        self.final_seconds = None;
+        self.generating = None;
+        Ok(())
+    }
+
+    fn get_seconds(&self) -> usize {
+        match (self.final_seconds, self.start) {
+            (Some(seconds), _) => seconds,
+            (None, Some(start)) => start.elapsed().as_secs() as usize,
+            (None, None) => 0,
+        }
+    }
+
+    fn apply(&mut self, action: Action) {
+        if let Some((board, start)) = self.game.get_no_guess_start(action) {
+            self.generating = Some((action, utils::spawn_no_guess_start(board, start)));
+            return;
+        }
+        let stage_before = self.game.get_stage();
+        self.game.apply(action);
+        let stage = self.game.get_stage();
+        if stage_before == GameStage::Pre && stage != GameStage::Pre {
+            self.start = Some(Instant::now());
+        }
+        if self.game.is_over() {
+            if self.final_seconds.is_none() {
+                self.final_seconds = Some(self.get_seconds());
+            }
+        } else {
+            // The end of the game was undone.
+            self.final_seconds = None;
+        }
+    }
+
+    fn chord(&mut self, pos: Pos) {
+        if self.game.get_board().get_tilestates()[pos] == TileState::Uncovered {
+            self.apply(Action::Chord(pos));
+        }
+    }
+
+    fn handle_input(&mut self, input: Input) {
+        let &(height, width) = self.game.get_board().get_size();
+        let (y, x) = self.cursor;
+        match input {
+            Input::Char('q') | Input::Char('\x03') => self.quit = true,
+            // Only quitting and starting a new game work while a board is
+            // being generated.
+            _ if self.generating.is_some() && input != Input::Char('r') => (),
+            Input::Char('r') => {
+                // The board was already made once with this difficulty, so
+                // this cannot fail.
+                let _ = self.new_game();
+            }
+            Input::Up | Input::Char('w') | Input::Char('k') => self.cursor.0 = y.saturating_sub(1),
+            Input::Down | Input::Char('s') | Input::Char('j') => {
+                self.cursor.0 = (y + 1).min(height - 1)
+            }
+            Input::Left | Input::Char('a') | Input::Char('h') => {
+                self.cursor.1 = x.saturating_sub(1)
+            }
+            Input::Right | Input::Char('d') | Input::Char('l') => {
+                self.cursor.1 = (x + 1).min(width - 1)
+            }
+            Input::PageUp => self.cursor.0 = 0,
+            Input::PageDown => self.cursor.0 = height - 1,
+            Input::Home => self.cursor.1 = 0,
+            Input::End => self.cursor.1 = width - 1,
+            Input::Char(' ') => {
+                let action = self.game.get_left_click_action(self.cursor);
+                self.apply(action);
+            }
+            Input::Char('f') => self.apply(Action::RightClick(self.cursor)),
+            Input::Char('c') => self.chord(self.cursor),
+            Input::Char('u') | Input::Char('\x1a') => self.apply(Action::Undo),
+            Input::Char('\x19') => self.apply(Action::Redo),
+            Input::Mouse(button, row, column) => {
+                if row < BOARD_ROW || column < BOARD_COLUMN {
+                    return;
+                }
+                // Hit-test the middle of the cell the same way as in a window,
+                // where a tile is a sprite wide and each row of text is
+                // across the middle of a row of tiles.
+                let board = self.game.get_board();
+                let point = (
+                    ((column - BOARD_COLUMN) as f32 + 0.5) * utils::TILE_SIZE
+                        / self.get_tile_width() as f32,
+                    (row - BOARD_ROW) as f32 * utils::get_row_height(board.get_topology())
+                        + utils::TILE_SIZE / 2.0,
+                );
+                let pos =
+                    match utils::get_tile_at_point(board.get_size(), board.get_topology(), point) {
+                        Some(pos) => pos,
+                        None => return,
+                    };
+                self.cursor = pos;
+                let uncovered = self.game.get_board().get_tilestates()[pos] == TileState::Uncovered;
+                if button == self.bindings.reveal {
+                    let action = self.game.get_left_click_action(pos);
+                    self.apply(action);
+                } else if button == self.bindings.flag {
+                    if self.bindings.chord_on_flag_click && uncovered {
+                        self.chord(pos);
+                    } else {
+                        self.apply(Action::RightClick(pos));
+                    }
+                } else if Some(button) == self.bindings.chord {
+                    self.chord(pos);
+                }
+            }
+            Input::Char(_) => (),
+        }
+    }
+
+    /// Return the number of columns of the terminal used by each tile, which
+    /// leaves a space before the longest text that any tile on the board could
+    /// show. This depends only on the rules, so that it does not give away
+    /// whether there are any large numbers.
+    fn get_tile_width(&self) -> usize {
+        let board = self.game.get_board();
+        let &(height, width) = board.get_size();
+        let mines_per_tile = board.get_mines_per_tile() as usize;
+        // The neighborhood includes the tile itself, so this is more than the
+        // largest number, which does not matter here.
+        let max_number = board.neighbor_coords((height / 2, width / 2)).count() * mines_per_tile;
+        // Flags and mines on a tile with more than one are followed by the
+        // count, which is a single digit.
+        let max_count_len = if mines_per_tile > 1 { 2 } else { 1 };
+        let tile_width = max_number.to_string().len().max(max_count_len) + 1;
+        // Odd rows of a hexagonal board are shifted by exactly half a tile.
+        match board.get_topology() {
+            Topology::Hex => tile_width + tile_width % 2,
+            _ => tile_width,
+        }
+    }
+
+    /// Return how many columns a row is shifted to the right, which is half a
+    /// tile for odd rows of a hexagonal board.
+    fn get_row_offset(&self, y: usize) -> usize {
+        match self.game.get_board().get_topology() {
+            Topology::Hex if y % 2 == 1 => self.get_tile_width() / 2,
+            _ => 0,
+        }
+    }
+
+    /// Parse the next complete input from the bytes read so far. Incomplete
+    /// escape sequences are left for the next read.
+    fn next_input(&mut self) -> Option<Input> {
+        loop {
+            let (input, len) = match self.pending.as_slice() {
+                [] => return None,
+                [b'\x1b', b'[', b'<', rest @ ..] => {
+                    let end = rest.iter().position(|&b| b == b'M' || b == b'm');
+                    match end {
+                        Some(end) => (parse_mouse(&rest[..end], rest[end]), end + 4),
+                        None if rest.len() < 16 => return None,
+                        None => (None, 3),
+                    }
+                }
+                [b'\x1b', b'[', b'A', ..] => (Some(Input::Up), 3),
+                [b'\x1b', b'[', b'B', ..] => (Some(Input::Down), 3),
+                [b'\x1b', b'[', b'C', ..] => (Some(Input::Right), 3),
+                [b'\x1b', b'[', b'D', ..] => (Some(Input::Left), 3),
+                [b'\x1b', b'[', b'H', ..] => (Some(Input::Home), 3),
+                [b'\x1b', b'[', b'F', ..] => (Some(Input::End), 3),
+                [b'\x1b', b'[', b'5', b'~', ..] => (Some(Input::PageUp), 4),
+                [b'\x1b', b'[', b'6', b'~', ..] => (Some(Input::PageDown), 4),
+                [b'\x1b', b'['] | [b'\x1b', b'[', b'5'] | [b'\x1b', b'[', b'6'] => return None,
+                // Skip any other escape sequence.
+                [b'\x1b', b'[', rest @ ..] => {
+                    match rest.iter().position(|b| (0x40..=0x7e).contains(b)) {
+                        Some(end) => (None, end + 3),
+                        None => return None,
+                    }
+                }
+                [b'\x1b', ..] => (None, 1),
+                [byte, ..] => (Some(Input::Char(*byte as char)), 1),
+            };
+            self.pending.drain(..len);
+            if input.is_some() {
+                return input;
+            }
+        }
+    }
+
+    fn draw(&self, stdout: &mut io::Stdout) -> io::Result<()> {
+        let board = self.game.get_board();
+        let stage = self.game.get_stage();
+        let mut out = String::from("\x1b[H");
+        let face = match stage {
+            GameStage::Pre | GameStage::Playing => ":)",
+            GameStage::Exploded => "X(",
+            GameStage::Complete => "B)",
+        };
+        let lives = if self.game.get_rules().lives > 1 {
+            format!("Lives: {:<3}", self.game.get_lives_left())
+        } else {
+            String::new()
+        };
+        out += &format!(
+            "Mines: {:<4} {}{}  Time: {}\x1b[K\r\n\x1b[K\r\n",
+            board.get_flags_left(),
+            lives,
+            face,
+            self.get_seconds()
+        );
+        let tile_width = self.get_tile_width();
+        for (y, row) in board.get_tilestates().genrows().into_iter().enumerate() {
+            out += &" ".repeat(self.get_row_offset(y));
+            for (x, &tilestate) in row.iter().enumerate() {
+                let pos = (y, x);
+                let (color, symbol) = tile_appearance(board.get_tiles()[pos], tilestate, stage);
+                if pos == self.cursor {
+                    out += "\x1b[7m";
+                }
+                out += &format!("{}{:>width$}{}", color, symbol, RESET, width = tile_width);
+            }
+            out += "\x1b[K\r\n";
+        }
+        out += "\x1b[K\r\n";
+        let status = match stage {
+            GameStage::Pre if self.generating.is_some() => {
+                "Generating a board that can be solved without guessing...".to_owned()
+            }
+            GameStage::Pre | GameStage::Playing => String::new(),
+            GameStage::Exploded => "You hit a mine.".to_owned(),
+            GameStage::Complete => {
+                let (solved, _) = stats::get_3bv(board);
+                let seconds = self.get_seconds().max(1);
+                format!("Cleared! 3BV/s: {:.2}", solved as f64 / seconds as f64)
+            }
+        };
+        out += &format!("{}\x1b[K\r\n", status);
+        out += "Move: arrows/WASD/HJKL  Space: uncover  F: flag  C: chord\x1b[K\r\n";
+        out += "U: undo  Ctrl+Y: redo  R: new game  Q: quit\x1b[K\x1b[J";
+        stdout.write_all(out.as_bytes())?;
+        stdout.flush()
+    }
+}
+
+/// Parse the body of an SGR mouse report, which looks like `0;12;5` followed by
+/// `M` for a press or `m` for a release. Only presses are used.
+fn parse_mouse(body: &[u8], kind: u8) -> Option<Input> {
+    if kind != b'M' {
+        return None;
+    }
+    let body = std::str::from_utf8(body).ok()?;
+    let mut parts = body.split(';').map(|part| part.parse::<usize>().ok());
+    let (code, column, row) = (parts.next()??, parts.next()??, parts.next()??);
+    let button = match code {
+        0 => Button::Left,
+        1 => Button::Middle,
+        2 => Button::Right,
+        // Ignore dragging, the scroll wheel, and modifier keys.
+        _ => return None,
+    };
+    Some(Input::Mouse(button, row, column))
+}
+
+/// Return the color escape sequence and text to draw for a tile. The text is one
+/// character, except for numbers above 9 and for tiles with more than one flag
+/// or mine, which are followed by the count.
+fn tile_appearance(tile: Tile, tilestate: TileState, stage: GameStage) -> (&'static str, String) {
+    let exploded = stage == GameStage::Exploded;
+    let (color, symbol) = match tilestate {
+        TileState::Hidden if exploded && tile.get_mines() > 1 => {
+            return ("\x1b[1m", format!("*{}", tile.get_mines()));
+        }
+        TileState::Hidden if exploded && tile.is_mine() => ("\x1b[1m", '*'),
+        TileState::Hidden => ("\x1b[2m", '.'),
+        TileState::Flagged(n) if exploded && tile.get_mines() != n => ("\x1b[1;31m", 'X'),
+        TileState::Flagged(n) if n > 1 => return ("\x1b[1;31m", format!("F{}", n)),
+        TileState::Flagged(_) => ("\x1b[1;31m", 'F'),
+        TileState::QuestionMark => ("\x1b[1m", '?'),
+        TileState::Uncovered => match tile {
+            Tile::Mine(n) if n > 1 => return ("\x1b[1;37;41m", format!("*{}", n)),
+            Tile::Mine(_) => ("\x1b[1;37;41m", '*'),
+            Tile::Safe(0) => ("", ' '),
+            Tile::Safe(n) => {
+                let color = match n {
+                    1 => "\x1b[1;34m",
+                    2 => "\x1b[32m",
+                    3 => "\x1b[1;31m",
+                    4 => "\x1b[35m",
+                    5 => "\x1b[31m",
+                    6 => "\x1b[36m",
+                    7 => "\x1b[1m",
+                    _ => "\x1b[2m",
+                };
+                return (color, n.to_string());
+            }
+        },
+    };
+    (color, symbol.to_string())
+}
@@ -1,29 +1,546 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
 use tetra::{
-    input::{self, MouseButton},
+    input::{self, Key, MouseButton},
     Context,
 };
 
-use crate::board::{Pos, Tile, TileState};
-use crate::GameStage;
+use minesweeper_engine::solver::Hint;
+use minesweeper_engine::{Action, Board, GameStage, Pos, Tile, TileState};
+
+use crate::bindings::Bindings;
+use crate::lifetime::DifficultyStats;
+use crate::save::{self, SavedGame};
+use crate::scores::{self, HighScore};
+use crate::utils;
 use crate::GameState;
 
+/// The maximum number of characters in a name entered for a high score.
+const MAX_NAME_LENGTH: usize = 16;
+
+/// A message or question shown on top of the board, which blocks input to the
+/// board until it is dismissed.
+pub enum Overlay {
+    /// Ask whether to resume a game that was saved when the window closed.
+    ResumePrompt(Box<SavedGame>),
+    /// Ask for a name to go with a new high score.
+    NameEntry { seconds: usize, name: String },
+    /// Show the best times for a difficulty.
+    HighScores {
+        difficulty_name: String,
+        scores: Vec<HighScore>,
+    },
+    /// Show the lifetime statistics for a difficulty.
+    Statistics {
+        difficulty_name: String,
+        stats: DifficultyStats,
+    },
+    /// Wait for a board that can be solved without guessing to be generated
+    /// on another thread, then apply the first click to it.
+    Generating {
+        action: Action,
+        receiver: Receiver<(Board, bool)>,
+    },
+}
+
+impl Overlay {
+    /// Return the lines of text to show.
+    pub fn get_text(&self) -> Vec<String> {
+        match self {
+            Overlay::ResumePrompt(_) => vec![
+                "Resume saved game?".to_owned(),
+                String::new(),
+                "Y: resume".to_owned(),
+                "N: discard".to_owned(),
+            ],
+            Overlay::NameEntry { seconds, name } => vec![
+                format!("New best time: {}s", seconds),
+                String::new(),
+                "Enter your name:".to_owned(),
+                format!("{}_", name),
+                String::new(),
+                "Enter: save".to_owned(),
+                "Esc: skip".to_owned(),
+            ],
+            Overlay::HighScores {
+                difficulty_name,
+                scores,
+            } => {
+                let mut lines = vec![format!("Best times: {}", difficulty_name), String::new()];
+                if scores.is_empty() {
+                    lines.push("No times yet".to_owned());
+                }
+                for (i, score) in scores.iter().enumerate() {
+                    lines.push(format!("{}. {} {}s", i + 1, score.name, score.seconds));
+                }
+                lines.push(String::new());
+                lines.push("Esc: close".to_owned());
+                lines
+            }
+            Overlay::Statistics {
+                difficulty_name,
+                stats,
+            } => {
+                let win_rate = stats
+                    .get_win_rate()
+                    .map_or("-".to_owned(), |rate| format!("{:.0}%", rate * 100.0));
+                let best = stats
+                    .best_seconds
+                    .map_or("-".to_owned(), |seconds| format!("{}s", seconds));
+                let average = stats
+                    .get_average_seconds()
+                    .map_or("-".to_owned(), |seconds| format!("{:.1}s", seconds));
+                vec![
+                    format!("Statistics: {}", difficulty_name),
+                    String::new(),
+                    format!("Played: {}", stats.played),
+                    format!("Won: {} ({})", stats.won, win_rate),
+                    format!("Lost: {}", stats.lost),
+                    format!(
+                        "Streak: {} (best {})",
+                        stats.current_streak, stats.best_streak
+                    ),
+                    format!("Best time: {}", best),
+                    format!("Average time: {}", average),
+                    String::new(),
+                    "Esc: close".to_owned(),
+                ]
+            }
+            Overlay::Generating { .. } => vec![
+                "Generating board...".to_owned(),
+                String::new(),
+                "Esc: cancel".to_owned(),
+            ],
+        }
+    }
+}
+
+/// The state of a chord made by pressing the reveal and flag buttons together.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DualChord {
+    Off,
+    /// Both buttons are held down.
+    Held,
+    /// One of the buttons was released, so the chord has happened, but the
+    /// other button is still held down.
+    Done,
+}
+
+/// The mouse buttons held down on a board, which is either a normal board or
+/// an infinite one, depending on the type of positions `P`.
+pub struct TileClicks<P> {
+    /// The tile under the cursor while the reveal button is held down.
+    pub left_clicked_tile: Option<P>,
+    right_clicked_tile: Option<P>,
+    dual_chord: DualChord,
+}
+
+impl<P> TileClicks<P> {
+    pub fn new() -> Self {
+        Self {
+            left_clicked_tile: None,
+            right_clicked_tile: None,
+            dual_chord: DualChord::Off,
+        }
+    }
+}
+
+/// A board whose tiles are clicked with the mouse. See `handle_tile_clicks`.
+pub trait ClickableBoard<P> {
+    fn get_tile_clicks(&mut self) -> &mut TileClicks<P>;
+    fn is_uncovered(&self, pos: P) -> bool;
+    fn reveal(&mut self, ctx: &mut Context, pos: P);
+    fn flag(&mut self, ctx: &mut Context, pos: P);
+    /// Uncover the neighbors of a tile if it is an uncovered number with the
+    /// right number of flags around it, and do nothing otherwise.
+    fn chord(&mut self, ctx: &mut Context, pos: P);
+}
+
+/// Handle presses and releases of every mouse button bound to an action on a
+/// board, given the tile under the cursor. A click happens when the button is
+/// released, on the tile under the cursor at that moment.
+pub fn handle_tile_clicks<P: Copy>(
+    board: &mut impl ClickableBoard<P>,
+    ctx: &mut Context,
+    bindings: &Bindings,
+    hover_tile: Option<P>,
+) {
+    let reveal_down = input::is_mouse_button_down(ctx, bindings.reveal.into());
+    let flag_down = input::is_mouse_button_down(ctx, bindings.flag.into());
+    let clicks = board.get_tile_clicks();
+    match clicks.dual_chord {
+        DualChord::Off => {
+            if bindings.reveal_flag_chord && reveal_down && flag_down {
+                clicks.dual_chord = DualChord::Held;
+                clicks.right_clicked_tile = None;
+            }
+        }
+        DualChord::Held => {
+            if reveal_down && flag_down {
+                clicks.left_clicked_tile = hover_tile;
+            } else {
+                // Chord as soon as either button is released.
+                clicks.left_clicked_tile = None;
+                clicks.dual_chord = DualChord::Done;
+                if let Some(hover_tile) = hover_tile {
+                    board.chord(ctx, hover_tile);
+                }
+            }
+        }
+        DualChord::Done => {
+            // Ignore the release of the other button.
+            if !reveal_down && !flag_down {
+                clicks.dual_chord = DualChord::Off;
+            }
+        }
+    }
+    if board.get_tile_clicks().dual_chord == DualChord::Off {
+        // The reveal button, which is the left mouse button by default.
+        let button = bindings.reveal.into();
+        let clicks = board.get_tile_clicks();
+        if clicks.left_clicked_tile.is_some() {
+            if input::is_mouse_button_down(ctx, button) {
+                clicks.left_clicked_tile = hover_tile;
+            } else if input::is_mouse_button_released(ctx, button) {
+                clicks.left_clicked_tile = None;
+                if let Some(hover_tile) = hover_tile {
+                    board.reveal(ctx, hover_tile);
+                }
+            }
+        } else if input::is_mouse_button_pressed(ctx, button) {
+            clicks.left_clicked_tile = hover_tile;
+        }
+
+        // The flag button, which is the right mouse button by default.
+        let button = bindings.flag.into();
+        let clicks = board.get_tile_clicks();
+        if clicks.right_clicked_tile.is_some() {
+            if input::is_mouse_button_down(ctx, button) {
+                clicks.right_clicked_tile = hover_tile;
+            } else if input::is_mouse_button_released(ctx, button) {
+                clicks.right_clicked_tile = None;
+                if let Some(hover_tile) = hover_tile {
+                    if bindings.chord_on_flag_click && board.is_uncovered(hover_tile) {
+                        board.chord(ctx, hover_tile);
+                    } else {
+                        board.flag(ctx, hover_tile);
+                    }
+                }
+            }
+        } else if input::is_mouse_button_pressed(ctx, button) {
+            clicks.right_clicked_tile = hover_tile;
+        }
+    }
+    // The chord button, which is the middle mouse button by default.
+    if let Some(button) = bindings.chord {
+        if input::is_mouse_button_released(ctx, button.into()) {
+            if let Some(hover_tile) = hover_tile {
+                board.chord(ctx, hover_tile);
+            }
+        }
+    }
+}
+
 pub struct UIState {
-    pub left_clicked_tile: Option<(usize, usize)>,
-    pub right_clicked_tile: Option<(usize, usize)>,
+    pub tile_clicks: TileClicks<Pos>,
     pub face_clicked: bool,
+    /// Whether to tint hidden tiles by their probability of being a mine.
+    pub show_probabilities: bool,
+    pub overlay: Option<Overlay>,
+    /// The tile selected with the keyboard, which is only shown once the
+    /// keyboard has been used.
+    pub keyboard_cursor: Option<Pos>,
+    /// The most recent hint, which is shown until the board changes.
+    pub hint: Option<Hint>,
+    /// Whether to flag tiles that must be mines because of a neighboring
+    /// number.
+    pub auto_flag: bool,
+    /// Whether to chord numbers that already have the right number of flags
+    /// around them.
+    pub auto_chord: bool,
 }
 
 impl UIState {
     pub fn new() -> Self {
         Self {
-            left_clicked_tile: None,
-            right_clicked_tile: None,
+            tile_clicks: TileClicks::new(),
             face_clicked: false,
+            show_probabilities: false,
+            overlay: None,
+            keyboard_cursor: None,
+            hint: None,
+            auto_flag: false,
+            auto_chord: false,
         }
     }
 }
 
+impl ClickableBoard<Pos> for GameState {
+    fn get_tile_clicks(&mut self) -> &mut TileClicks<Pos> {
+        &mut self.ui_state.tile_clicks
+    }
+
+    fn is_uncovered(&self, pos: Pos) -> bool {
+        self.game.get_board().get_tilestates()[pos] == TileState::Uncovered
+    }
+
+    fn reveal(&mut self, ctx: &mut Context, pos: Pos) {
+        self.left_click_tile(ctx, pos);
+    }
+
+    fn flag(&mut self, ctx: &mut Context, pos: Pos) {
+        self.right_click_tile(ctx, pos);
+    }
+
+    fn chord(&mut self, ctx: &mut Context, pos: Pos) {
+        self.chord_tile(ctx, pos);
+    }
+}
+
 impl GameState {
+    pub fn handle_overlay_keys(&mut self, ctx: &mut Context) {
+        let yes = input::is_key_pressed(ctx, Key::Y) || input::is_key_pressed(ctx, Key::Return);
+        let no = input::is_key_pressed(ctx, Key::N) || input::is_key_pressed(ctx, Key::Escape);
+        match self.ui_state.overlay.take() {
+            Some(Overlay::ResumePrompt(saved_game)) => {
+                if yes || no {
+                    // The game is saved again when the window closes if it is
+                    // still in progress.
+                    save::delete();
+                    if yes {
+                        self.resume(ctx, *saved_game);
+                    }
+                } else {
+                    self.ui_state.overlay = Some(Overlay::ResumePrompt(saved_game));
+                }
+            }
+            Some(Overlay::NameEntry { seconds, mut name }) => {
+                if input::is_key_pressed(ctx, Key::Return) {
+                    let name = match name.trim() {
+                        "" => "Anonymous".to_owned(),
+                        trimmed => trimmed.to_owned(),
+                    };
+                    let no_guess = self.is_no_guess();
+                    self.high_scores
+                        .add(&self.difficulty, no_guess, HighScore { name, seconds });
+                    scores::store(&self.high_scores);
+                    self.show_high_scores();
+                } else if !input::is_key_pressed(ctx, Key::Escape) {
+                    if input::is_key_pressed(ctx, Key::Backspace) {
+                        name.pop();
+                    }
+                    if let Some(text) = input::get_text_input(ctx) {
+                        name.extend(text.chars().take(MAX_NAME_LENGTH - name.chars().count()));
+                    }
+                    self.ui_state.overlay = Some(Overlay::NameEntry { seconds, name });
+                }
+            }
+            // The keys that show these overlays also close them.
+            Some(Overlay::HighScores { .. }) | Some(Overlay::Statistics { .. })
+                if no
+                    || input::is_key_pressed(ctx, Key::Return)
+                    || input::is_key_pressed(ctx, Key::B)
+                    || input::is_key_pressed(ctx, Key::T) => {}
+            overlay @ Some(Overlay::HighScores { .. })
+            | overlay @ Some(Overlay::Statistics { .. }) => self.ui_state.overlay = overlay,
+            // The board is left as it was if generating it is cancelled.
+            Some(Overlay::Generating { .. }) if no => (),
+            Some(Overlay::Generating { action, receiver }) => match receiver.try_recv() {
+                Ok((board, solvable)) => {
+                    self.game.set_start_board(board, solvable);
+                    self.apply_action(ctx, action);
+                }
+                Err(TryRecvError::Empty) => {
+                    self.ui_state.overlay = Some(Overlay::Generating { action, receiver })
+                }
+                // The thread panicked, so the click is dropped.
+                Err(TryRecvError::Disconnected) => println!("Could not generate a board"),
+            },
+            None => (),
+        }
+    }
+
+    /// Show the best times for the current difficulty.
+    pub fn show_high_scores(&mut self) {
+        let no_guess = self.is_no_guess();
+        let mut difficulty_name = self.difficulty.get_name();
+        if no_guess {
+            difficulty_name.push_str(" (no guess)");
+        }
+        self.ui_state.overlay = Some(Overlay::HighScores {
+            difficulty_name,
+            scores: self.high_scores.get(&self.difficulty, no_guess).to_vec(),
+        });
+    }
+
+    /// Show the lifetime statistics for the current difficulty and rules.
+    pub fn show_statistics(&mut self) {
+        let no_guess = self.is_no_guess();
+        let lives = self.game.get_rules().lives;
+        let mut difficulty_name = self.difficulty.get_name();
+        if no_guess {
+            difficulty_name.push_str(" (no guess)");
+        }
+        if lives > 1 {
+            difficulty_name.push_str(&format!(" ({} lives)", lives));
+        }
+        self.ui_state.overlay = Some(Overlay::Statistics {
+            difficulty_name,
+            stats: self.lifetime_stats.get(&self.difficulty, no_guess, lives),
+        });
+    }
+
+    /// Handle the keys for probabilities (P), hints (I), the assist modes (F3
+    /// and F4), best times (B), statistics (T), and undo and redo. None of
+    /// these are used by the keyboard cursor (see `handle_cursor_keys`), so
+    /// both can be handled on the same frame.
+    pub fn handle_keys(&mut self, ctx: &mut Context) {
+        if input::is_key_pressed(ctx, Key::P) {
+            if 1 < self.game.get_board().get_mines_per_tile() {
+                println!("Probabilities are not available when a tile can hold more than one mine");
+            } else {
+                self.ui_state.show_probabilities = !self.ui_state.show_probabilities;
+            }
+        }
+        if input::is_key_pressed(ctx, Key::I) {
+            self.show_hint(ctx);
+        }
+        if input::is_key_pressed(ctx, Key::F3) {
+            self.ui_state.auto_flag = !self.ui_state.auto_flag;
+            println!("Auto-flag: {}", on_off(self.ui_state.auto_flag));
+            self.apply_assists(ctx);
+        }
+        if input::is_key_pressed(ctx, Key::F4) {
+            self.ui_state.auto_chord = !self.ui_state.auto_chord;
+            println!("Auto-chord: {}", on_off(self.ui_state.auto_chord));
+            self.apply_assists(ctx);
+        }
+        if input::is_key_pressed(ctx, Key::B) {
+            self.show_high_scores();
+        } else if input::is_key_pressed(ctx, Key::T) {
+            self.show_statistics();
+        }
+        let ctrl = input::is_key_down(ctx, Key::LCtrl) || input::is_key_down(ctx, Key::RCtrl);
+        let shift = input::is_key_down(ctx, Key::LShift) || input::is_key_down(ctx, Key::RShift);
+        if ctrl && input::is_key_pressed(ctx, Key::Z) {
+            if shift {
+                self.redo(ctx);
+            } else {
+                self.undo(ctx);
+            }
+        } else if ctrl && input::is_key_pressed(ctx, Key::Y) {
+            self.redo(ctx);
+        }
+    }
+
+    /// Move the keyboard cursor with the arrow keys, WASD, or HJKL (or to the
+    /// edge of the board while holding shift, or with Home, End, Page Up, and
+    /// Page Down), and apply actions to the tile under it. R or F2 starts a new
+    /// game. Using the mouse hides the cursor.
+    pub fn handle_cursor_keys(&mut self, ctx: &mut Context) {
+        if input::is_mouse_button_pressed(ctx, self.bindings.reveal.into())
+            || input::is_mouse_button_pressed(ctx, self.bindings.flag.into())
+        {
+            self.ui_state.keyboard_cursor = None;
+        }
+        let ctrl = input::is_key_down(ctx, Key::LCtrl) || input::is_key_down(ctx, Key::RCtrl);
+        if ctrl {
+            return;
+        }
+        if input::is_key_pressed(ctx, Key::R) || input::is_key_pressed(ctx, Key::F2) {
+            self.reset_game(ctx);
+        }
+
+        let shift = input::is_key_down(ctx, Key::LShift) || input::is_key_down(ctx, Key::RShift);
+        let pressed = |keys: &[Key]| keys.iter().any(|&key| input::is_key_pressed(ctx, key));
+
+        let up = pressed(&[Key::Up, Key::W, Key::K]);
+        let down = pressed(&[Key::Down, Key::S, Key::J]);
+        let left = pressed(&[Key::Left, Key::A, Key::H]);
+        let right = pressed(&[Key::Right, Key::D, Key::L]);
+        let top = pressed(&[Key::PageUp]) || (shift && up);
+        let bottom = pressed(&[Key::PageDown]) || (shift && down);
+        let start = pressed(&[Key::Home]) || (shift && left);
+        let end = pressed(&[Key::End]) || (shift && right);
+        let reveal = pressed(&[Key::Space]);
+        let flag = pressed(&[Key::F]);
+        let chord = pressed(&[Key::C]);
+
+        let &(height, width) = self.game.get_board().get_size();
+        let (mut y, mut x) = match self.ui_state.keyboard_cursor {
+            // The board may have changed size since the cursor was last used.
+            Some((y, x)) => (y.min(height - 1), x.min(width - 1)),
+            // The first key press only shows the cursor, in the middle of the
+            // board.
+            None => {
+                let any = [
+                    up, down, left, right, top, bottom, start, end, reveal, flag, chord,
+                ];
+                if any.iter().any(|&key| key) {
+                    self.ui_state.keyboard_cursor = Some((height / 2, width / 2));
+                }
+                return;
+            }
+        };
+        if top {
+            y = 0;
+        } else if bottom {
+            y = height - 1;
+        } else if up {
+            y = y.saturating_sub(1);
+        } else if down {
+            y = (y + 1).min(height - 1);
+        }
+        if start {
+            x = 0;
+        } else if end {
+            x = width - 1;
+        } else if left {
+            x = x.saturating_sub(1);
+        } else if right {
+            x = (x + 1).min(width - 1);
+        }
+        let pos = (y, x);
+        self.ui_state.keyboard_cursor = Some(pos);
+
+        if !self.game.is_over() {
+            if reveal {
+                self.left_click_tile(ctx, pos);
+            } else if flag {
+                self.right_click_tile(ctx, pos);
+            } else if chord {
+                self.chord_tile(ctx, pos);
+            }
+        }
+    }
+
+    /// Highlight a tile that can be proven to be safe or a mine, and print
+    /// which numbers prove it. This marks the game as assisted.
+    pub fn show_hint(&mut self, ctx: &mut Context) {
+        if self.game.get_stage() != GameStage::Playing {
+            return;
+        }
+        self.ui_state.hint = self.game.hint();
+        match &self.ui_state.hint {
+            Some(hint) => println!("{}", describe_hint(self.game.get_board(), hint)),
+            None if 1 < self.game.get_board().get_mines_per_tile() => {
+                println!("Hint: not available when a tile can hold more than one mine")
+            }
+            None => println!("Hint: no tile can be proven safe or a mine"),
+        }
+        self.show_assisted(ctx);
+    }
+
+    /// Undo the most recent action, if there is one. This marks the game as
+    /// assisted.
+    pub fn undo(&mut self, ctx: &mut Context) {
+        self.apply_action(ctx, Action::Undo);
+    }
+
+    /// Redo the most recently undone action, if there is one.
+    pub fn redo(&mut self, ctx: &mut Context) {
+        self.apply_action(ctx, Action::Redo);
+    }
+
     pub fn handle_face_click(&mut self, ctx: &mut Context) {
         if self.is_mouse_on_face(ctx) {
             if input::is_mouse_button_pressed(ctx, MouseButton::Left) {
@@ -37,48 +554,157 @@ impl GameState {
         }
     }
 
-    pub fn handle_tile_left_click(&mut self, ctx: &mut Context) -> Vec<Pos> {
+    /// Handle presses and releases of every mouse button bound to an action on
+    /// the board.
+    pub fn handle_tile_clicks(&mut self, ctx: &mut Context) {
         let hover_tile = self.get_tile_at_cursor(ctx);
-        if let Some(_) = self.ui_state.left_clicked_tile {
-            if input::is_mouse_button_down(ctx, MouseButton::Left) {
-                self.ui_state.left_clicked_tile = hover_tile;
-            } else if input::is_mouse_button_released(ctx, MouseButton::Left) {
-                self.ui_state.left_clicked_tile = None;
-                if let Some(hover_tile) = hover_tile {
-                    if self.stage == GameStage::Pre {
-                        self.board.ensure_safe_start(hover_tile);
-                        self.stage = GameStage::Playing;
-                    }
-                    let updated_squares = self.board.left_click(hover_tile);
-                    for pos in updated_squares {
-                        if self.board.get_tiles()[pos] == Tile::Mine
-                            && self.board.get_tilestates()[pos] == TileState::Uncovered
-                        {
-                            self.stage = GameStage::Exploded;
-                        }
-                    }
-                }
-            }
-        } else if input::is_mouse_button_pressed(ctx, MouseButton::Left) {
-            self.ui_state.left_clicked_tile = hover_tile;
+        let bindings = self.bindings;
+        handle_tile_clicks(self, ctx, &bindings, hover_tile);
+    }
+
+    /// Uncover the neighbors of a tile if it is an uncovered number with the
+    /// right number of flags around it, and do nothing otherwise. Return the
+    /// coordinates of the tiles that changed state.
+    pub fn chord_tile(&mut self, ctx: &mut Context, pos: Pos) -> Vec<Pos> {
+        if self.game.get_board().get_tilestates()[pos] == TileState::Uncovered {
+            self.apply_action(ctx, Action::Chord(pos))
+        } else {
+            vec![]
         }
-        vec![]
     }
 
-    pub fn handle_tile_right_click(&mut self, ctx: &mut Context) -> Vec<Pos> {
-        let hover_tile = self.get_tile_at_cursor(ctx);
-        if let Some(_) = self.ui_state.right_clicked_tile {
-            if input::is_mouse_button_down(ctx, MouseButton::Right) {
-                self.ui_state.right_clicked_tile = hover_tile;
-            } else if input::is_mouse_button_released(ctx, MouseButton::Right) {
-                self.ui_state.right_clicked_tile = None;
-                if let Some(hover_tile) = hover_tile {
-                    return self.board.right_click(hover_tile);
-                }
+    /// Apply a left click to a tile (which may be a chord, if the tile is
+    /// already uncovered). Return the coordinates of the tiles that changed
+    /// state.
+    pub fn left_click_tile(&mut self, ctx: &mut Context, pos: Pos) -> Vec<Pos> {
+        let action = self.game.get_left_click_action(pos);
+        self.apply_action(ctx, action)
+    }
+
+    /// Apply a right click to a tile. Return the coordinates of the tiles that
+    /// changed state.
+    pub fn right_click_tile(&mut self, ctx: &mut Context, pos: Pos) -> Vec<Pos> {
+        self.apply_action(ctx, Action::RightClick(pos))
+    }
+
+    /// Apply an action to the game and record it in the replay, if it is
+    /// allowed, followed by any moves made by the assist modes. Return the
+    /// coordinates of the tiles that changed state.
+    pub fn apply_action(&mut self, ctx: &mut Context, action: Action) -> Vec<Pos> {
+        if let Some((board, start)) = self.game.get_no_guess_start(action) {
+            self.ui_state.overlay = Some(Overlay::Generating {
+                action,
+                receiver: utils::spawn_no_guess_start(board, start),
+            });
+            return vec![];
+        }
+        let mut updated_squares = self.apply_single_action(ctx, action);
+        let assist = match action {
+            Action::LeftClick(_) | Action::Chord(_) => true,
+            // Only assist after placing a flag, so that a flag that the
+            // player removes is not put right back.
+            Action::RightClick(pos) => matches!(
+                self.game.get_board().get_tilestates()[pos],
+                TileState::Flagged(_)
+            ),
+            Action::Undo | Action::Redo => false,
+        };
+        if assist && !updated_squares.is_empty() {
+            updated_squares.extend(self.apply_assists(ctx));
+        }
+        updated_squares
+    }
+
+    /// Apply an action to the game and record it in the replay, if it is
+    /// allowed. Return the coordinates of the tiles that changed state.
+    fn apply_single_action(&mut self, ctx: &mut Context, action: Action) -> Vec<Pos> {
+        let stage_before = self.game.get_stage();
+        let lives_before = self.game.get_lives_left();
+        let updated_squares = match self.game.apply(action) {
+            Some(updated_squares) => updated_squares,
+            None => return vec![],
+        };
+        self.recorder.record(action);
+        self.ui_state.hint = None;
+        if stage_before == GameStage::Pre && self.game.did_no_guess_fail() {
+            println!("Could not find a board that can be solved without guessing");
+        }
+        let lives_left = self.game.get_lives_left();
+        if 0 < lives_left && lives_left < lives_before {
+            println!("Lost a life ({} left)", lives_left);
+            self.show_assisted(ctx);
+        }
+        if action == Action::Undo {
+            self.show_assisted(ctx);
+        }
+        updated_squares
+    }
+
+    /// Keep flagging and chording for the player, as allowed by the assist
+    /// modes that are on, until there is nothing left to do. Moves made this
+    /// way are recorded like any other, and mark the game as assisted. Replays
+    /// are played back exactly as recorded, without any assists. Return the
+    /// coordinates of the tiles that changed state.
+    pub fn apply_assists(&mut self, ctx: &mut Context) -> Vec<Pos> {
+        let mut updated_squares = vec![];
+        if self.player.is_some() {
+            return updated_squares;
+        }
+        loop {
+            let mut action = None;
+            if self.ui_state.auto_flag {
+                action = self.game.find_auto_flag();
+            }
+            if action.is_none() && self.ui_state.auto_chord {
+                action = self.game.find_auto_chord();
+            }
+            let action = match action {
+                Some(action) => action,
+                None => break,
+            };
+            let changed = self.apply_single_action(ctx, action);
+            if changed.is_empty() {
+                break;
             }
-        } else if input::is_mouse_button_pressed(ctx, MouseButton::Right) {
-            self.ui_state.right_clicked_tile = hover_tile;
+            updated_squares.extend(changed);
+            self.game.mark_assisted();
+            self.show_assisted(ctx);
         }
-        vec![]
+        updated_squares
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Return a sentence that explains a hint, with rows and columns counted from 1.
+fn describe_hint(board: &Board, hint: &Hint) -> String {
+    let describe_pos = |(y, x): Pos| format!("row {}, column {}", y + 1, x + 1);
+    let numbers: Vec<String> = hint
+        .reasons
+        .iter()
+        .map(|&pos| match board.get_tiles()[pos] {
+            Tile::Safe(n) => format!("the {} at {}", n, describe_pos(pos)),
+            Tile::Mine(_) => format!("the mine at {}", describe_pos(pos)),
+        })
+        .collect();
+    let mut reasons = match numbers.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        None => "the number of mines left".to_owned(),
+    };
+    if hint.needs_more && !numbers.is_empty() {
+        reasons.push_str(", along with numbers further away and the number of mines left");
     }
+    format!(
+        "Hint: the tile at {} is {}, because of {}",
+        describe_pos(hint.pos),
+        if hint.is_mine { "a mine" } else { "safe" },
+        reasons
+    )
 }
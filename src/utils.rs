@@ -1,12 +1,14 @@
+use minesweeper_engine::{Board, Pos, Topology};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
 use tetra::graphics::Vec2;
 
-pub fn vec2_from_reverse_usize_tuple(&(y, x): &(usize, usize)) -> Vec2 {
-    vec2_from_usize_tuple(&(x, y))
-}
-
-pub fn vec2_from_usize_tuple(tuple: &(usize, usize)) -> Vec2 {
-    Vec2::new(tuple.0 as f32, tuple.1 as f32)
-}
+/// The width and height of a tile sprite, in pixels.
+pub const TILE_SIZE: f32 = 16.0;
+/// The distance between the rows of a hexagonal board, in pixels. Rows overlap
+/// by a quarter of a tile, so that the point at the bottom of each tile fits
+/// between two tiles in the next row.
+pub const HEX_ROW_HEIGHT: f32 = 12.0;
 
 pub fn vec2_from_tuple(tuple: &(f32, f32)) -> Vec2 {
     Vec2::new(tuple.0, tuple.1)
@@ -15,3 +17,82 @@ pub fn vec2_from_tuple(tuple: &(f32, f32)) -> Vec2 {
 pub fn i32_tuple_from_vec2(vec2: &Vec2) -> (i32, i32) {
     (vec2[0] as i32, vec2[1] as i32)
 }
+
+/// Return the distance between the tops of two rows of tiles, in pixels.
+pub fn get_row_height(topology: &Topology) -> f32 {
+    match topology {
+        Topology::Hex => HEX_ROW_HEIGHT,
+        _ => TILE_SIZE,
+    }
+}
+
+/// Return how far a row is shifted to the right, in pixels, which is half a
+/// tile for odd rows of a hexagonal board.
+pub fn get_row_offset(topology: &Topology, y: usize) -> f32 {
+    match topology {
+        Topology::Hex if y % 2 == 1 => TILE_SIZE / 2.0,
+        _ => 0.0,
+    }
+}
+
+/// Return the tile at a point, measured in pixels from the top left corner of
+/// the board, if there is one. The top of a hexagonal tile overlaps the row
+/// above it, so the point is tested against the hexagons in both rows.
+pub fn get_tile_at_point(
+    &(height, width): &(usize, usize),
+    topology: &Topology,
+    (point_x, point_y): (f32, f32),
+) -> Option<Pos> {
+    if point_x < 0.0 || point_y < 0.0 {
+        return None;
+    }
+    let row_height = get_row_height(topology);
+    let row = (point_y / row_height).floor() as usize;
+    (row.saturating_sub(1)..=row).find_map(|y| {
+        let row_x = point_x - get_row_offset(topology, y);
+        if row_x < 0.0 {
+            return None;
+        }
+        let x = (row_x / TILE_SIZE).floor() as usize;
+        let tile_point = (
+            row_x - x as f32 * TILE_SIZE,
+            point_y - y as f32 * row_height,
+        );
+        if y < height && x < width && tile_contains(topology, tile_point) {
+            Some((y, x))
+        } else {
+            None
+        }
+    })
+}
+
+/// Return whether a point, measured in pixels from the top left corner of a
+/// tile sprite, is on the tile. Hexagonal tiles have a point at the top and
+/// bottom, which take up a quarter of the height each.
+fn tile_contains(topology: &Topology, (x, y): (f32, f32)) -> bool {
+    if !(0.0..TILE_SIZE).contains(&x) || !(0.0..TILE_SIZE).contains(&y) {
+        return false;
+    }
+    match topology {
+        Topology::Hex => {
+            let point_height = TILE_SIZE / 4.0;
+            let from_end = y.min(TILE_SIZE - y);
+            from_end >= point_height
+                || (x - TILE_SIZE / 2.0).abs() <= from_end * TILE_SIZE / 2.0 / point_height
+        }
+        _ => true,
+    }
+}
+
+/// Arrange the mines on a board on another thread, so that it can be solved
+/// from a tile without guessing, since this can take a few seconds. The board
+/// is sent back once it is ready, along with whether it can be solved.
+pub fn spawn_no_guess_start(mut board: Board, start: Pos) -> Receiver<(Board, bool)> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let solvable = board.ensure_no_guess_start(start);
+        // Nothing is waiting for the board if the game was abandoned.
+        let _ = sender.send((board, solvable));
+    });
+    receiver
+}